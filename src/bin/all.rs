@@ -0,0 +1,146 @@
+//! Run every day on its input and print a summary of the answers.
+//!
+//! Inputs are read from `<dir>/dayNN.txt`, `<dir>` defaulting to `inputs/`.
+//! Days without an input file are skipped.
+
+use std::time::{Duration, Instant};
+
+use advent::{Answers, input_dir, normalize_newlines, silence_debug_output};
+
+#[allow(dead_code)]
+#[path = "day01.rs"]
+mod day01;
+#[allow(dead_code)]
+#[path = "day02/main.rs"]
+mod day02;
+#[allow(dead_code)]
+#[path = "day03.rs"]
+mod day03;
+#[allow(dead_code)]
+#[path = "day04.rs"]
+mod day04;
+#[allow(dead_code)]
+#[path = "day05.rs"]
+mod day05;
+#[allow(dead_code)]
+#[path = "day06.rs"]
+mod day06;
+#[allow(dead_code)]
+#[path = "day07.rs"]
+mod day07;
+#[allow(dead_code)]
+#[path = "day08.rs"]
+mod day08;
+#[allow(dead_code)]
+#[path = "day09.rs"]
+mod day09;
+#[allow(dead_code)]
+#[path = "day10.rs"]
+mod day10;
+#[allow(dead_code)]
+#[path = "day11.rs"]
+mod day11;
+#[allow(dead_code)]
+#[path = "day12.rs"]
+mod day12;
+#[allow(dead_code)]
+#[path = "day13.rs"]
+mod day13;
+#[allow(dead_code)]
+#[path = "day14.rs"]
+mod day14;
+#[allow(dead_code)]
+#[path = "day15.rs"]
+mod day15;
+#[allow(dead_code)]
+#[path = "day16.rs"]
+mod day16;
+#[allow(dead_code)]
+#[path = "day17.rs"]
+mod day17;
+#[allow(dead_code)]
+#[path = "day18.rs"]
+mod day18;
+#[allow(dead_code)]
+#[path = "day19.rs"]
+mod day19;
+
+type Solver = fn(&str) -> anyhow::Result<Answers>;
+
+const DAYS: [(&str, Solver); 19] = [
+    ("day01", day01::run),
+    ("day02", day02::run),
+    ("day03", day03::run),
+    ("day04", day04::run),
+    ("day05", day05::run),
+    ("day06", day06::run),
+    ("day07", day07::run),
+    ("day08", day08::run),
+    ("day09", day09::run),
+    ("day10", day10::run),
+    ("day11", day11::run),
+    ("day12", day12::run),
+    ("day13", day13::run),
+    ("day14", day14::run),
+    ("day15", day15::run),
+    ("day16", day16::run),
+    ("day17", day17::run),
+    ("day18", day18::run),
+    ("day19", day19::run),
+];
+
+fn main() -> anyhow::Result<()> {
+    let dir = input_dir()?;
+
+    // Per-day debug output would bury the summary
+    silence_debug_output();
+
+    let mut rows: Vec<(&str, Answers, Duration)> = Vec::new();
+    for (day, run) in DAYS {
+        let path = dir.join(format!("{day}.txt"));
+        let input = match std::fs::read_to_string(&path) {
+            Ok(input) => normalize_newlines(input),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                eprintln!("warning: skipping {day}, {} does not exist", path.display());
+                continue;
+            },
+            Err(err) => return Err(err.into()),
+        };
+
+        let start = Instant::now();
+        match run(&input) {
+            Ok(answers) => rows.push((day, answers, start.elapsed())),
+            Err(err) => eprintln!("warning: {day} failed: {err:#}"),
+        }
+    }
+
+    print_summary(&rows);
+
+    Ok(())
+}
+
+fn print_summary(rows: &[(&str, Answers, Duration)]) {
+    let cells: Vec<[String; 4]> = rows.iter()
+        .map(|(day, answers, elapsed)| [
+            day.to_string(),
+            answers.silver.clone().unwrap_or_else(|| "-".to_string()),
+            answers.gold.clone().unwrap_or_else(|| "-".to_string()),
+            format!("{:.2}ms", elapsed.as_secs_f64() * 1000.0),
+        ])
+        .collect();
+
+    let header = ["day", "silver", "gold", "time"].map(String::from);
+    let mut widths = header.each_ref().map(|cell| cell.len());
+    for row in &cells {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.len());
+        }
+    }
+
+    for row in std::iter::once(&header).chain(&cells) {
+        let line: Vec<String> = row.iter().zip(widths)
+            .map(|(cell, width)| format!("{cell:<width$}"))
+            .collect();
+        println!("{}", line.join(" | ").trim_end());
+    }
+}
@@ -1,69 +1,199 @@
-use advent::read_input;
-use aho_corasick::AhoCorasick;
-
-/// Get first and last element of an iterator.
-/// If iterator only has one item, returns first item twice.
-/// 
-/// Returns [`None`] if iterator is empty.
-fn iter_first_last<I: Clone>(mut iter: impl Iterator<Item=I>) -> Option<(I, I)> {
-    let Some(first) = iter.next() else {
-        return None
-    };
+use advent::{read_input, run_repeated, part_filter, Answers, timed};
+use aho_corasick::{AhoCorasick, Match};
 
-    let Some(last) = iter.last() else {
-        return Some((first.clone(), first));
-    };
+/// Digit patterns and their values for silver.
+const SILVER_DIGITS: [(&str, usize); 9] = [
+    ("1", 1), ("2", 2), ("3", 3), ("4", 4), ("5", 5), ("6", 6), ("7", 7), ("8", 8), ("9", 9),
+];
+
+/// Digit patterns and their values for gold, includes spelled out digits.
+const GOLD_DIGITS: [(&str, usize); 18] = [
+    ("1", 1), ("2", 2), ("3", 3), ("4", 4), ("5", 5), ("6", 6), ("7", 7), ("8", 8), ("9", 9),
+    ("one", 1), ("two", 2), ("three", 3), ("four", 4), ("five", 5),
+    ("six", 6), ("seven", 7), ("eight", 8), ("nine", 9),
+];
+
+/// Get the match starting first and the match ending last.
+/// If there is only one match, returns it twice.
+///
+/// Overlapping matches are reported in the order they end, so a pattern
+/// may be reported after a shorter one nested inside of it.
+/// Matches ending at the same position are ordered by their start.
+///
+/// Returns [`None`] if there are no matches.
+fn first_last_match(mut matches: impl Iterator<Item = Match>) -> Option<(Match, Match)> {
+    let first = matches.next()?;
 
-    Some((first, last))
+    Some(matches.fold((first, first), |(first, last), found| (
+        if found.start() < first.start() { found } else { first },
+        if (found.end(), found.start()) > (last.end(), last.start()) { found } else { last },
+    )))
 }
 
-fn solve_line(line: &str, ac: &AhoCorasick) -> usize {
-    let res = iter_first_last(ac.find_overlapping_iter(line));
+/// Calculate calibration value of a single line.
+///
+/// `ac` must have been built from the patterns of `digits`, in the same order.
+///
+/// Gold depends on `overlapping` matches, so that e.g. `oneight` ends in `eight`.
+/// Without them, a match hides any other match starting inside of it.
+///
+/// Returns [`None`] if line has no digits in it.
+fn solve_line(line: &str, ac: &AhoCorasick, digits: &[(&str, usize)], overlapping: bool) -> Option<usize> {
+    let res = if overlapping {
+        first_last_match(ac.find_overlapping_iter(line))
+    } else {
+        first_last_match(ac.find_iter(line))
+    };
     res.map(|pair| {
         // Convert pattern ID into a numeric value
         let numeric = (
-            pair.0.pattern().as_usize() % 9 + 1,
-            pair.1.pattern().as_usize() % 9 + 1,
+            digits[pair.0.pattern().as_usize()].1,
+            digits[pair.1.pattern().as_usize()].1,
         );
         numeric.0*10 + numeric.1
-    }).unwrap()
+    })
+}
+
+/// Calculate calibration value of a single line without Aho-Corasick.
+///
+/// Scans forward for the first pattern of [`GOLD_DIGITS`]
+/// and backward for the last one.
+/// Returns [`None`] if line has no digits in it.
+#[cfg(any(test, feature = "manual-scan"))]
+fn solve_line_manual(line: &str) -> Option<usize> {
+    // Digit starting at byte index `i`, if any
+    let digit_at = |i: usize| -> Option<usize> {
+        let rest = &line[i..];
+        GOLD_DIGITS.iter()
+            .find(|(pattern, _)| rest.starts_with(pattern))
+            .map(|&(_, value)| value)
+    };
+
+    // Only consider char boundaries, so slicing `line` never panics
+    let indices = line.char_indices().map(|(i, _)| i);
+    let first = indices.clone().find_map(digit_at)?;
+    let last = indices.rev().find_map(digit_at)?;
+
+    Some(first*10 + last)
 }
 
+/// Sum calibration values of all lines using given `(pattern, value)` pairs.
+/// Lines without any digits (including empty ones) contribute 0.
 #[cfg(feature = "parallel")]
-fn solve(input: &str, ac: &AhoCorasick) -> usize {
+fn solve(input: &str, digits: &[(&str, usize)], overlapping: bool) -> usize {
     use rayon::prelude::*;
-    input.trim().par_lines().map(|line| solve_line(line, ac)).sum()
+    let ac = AhoCorasick::new(digits.iter().map(|(pattern, _)| pattern)).unwrap();
+    input.trim().par_lines().filter_map(|line| solve_line(line, &ac, digits, overlapping)).sum()
 }
 
+/// Sum calibration values of all lines using given `(pattern, value)` pairs.
+/// Lines without any digits (including empty ones) contribute 0.
 #[cfg(not(feature = "parallel"))]
-fn solve(input: &str, ac: &AhoCorasick) -> usize {
-    input.trim().lines().map(|line| solve_line(line, ac)).sum()
+fn solve(input: &str, digits: &[(&str, usize)], overlapping: bool) -> usize {
+    let ac = AhoCorasick::new(digits.iter().map(|(pattern, _)| pattern)).unwrap();
+    input.trim().lines().filter_map(|line| solve_line(line, &ac, digits, overlapping)).sum()
 }
 
 fn silver(input: &str) -> usize {
-    const DIGITS: [&str; 9] = [
-        "1", "2", "3", "4", "5", "6", "7", "8", "9",
-    ];
-    let ac = AhoCorasick::new(DIGITS).unwrap();
+    solve(input, &SILVER_DIGITS, true)
+}
 
-    solve(input, &ac)
+#[cfg(feature = "manual-scan")]
+fn gold(input: &str) -> usize {
+    input.trim().lines().filter_map(solve_line_manual).sum()
 }
 
+#[cfg(not(feature = "manual-scan"))]
 fn gold(input: &str) -> usize {
-    const DIGITS: [&str; 18] = [
-        "1", "2", "3", "4", "5", "6", "7", "8", "9", 
-        "one", "two", "three", "four", "five", "six", "seven", "eight", "nine",
-    ];
-    let ac = AhoCorasick::new(DIGITS).unwrap();
+    solve(input, &GOLD_DIGITS, true)
+}
+
+/// Solve the puzzle for given input.
+pub fn run(input: &str) -> anyhow::Result<Answers> {
+    let part = part_filter();
 
-    solve(input, &ac)
+    Ok(Answers::new(
+        part.silver(|| timed("silver", || silver(input))),
+        part.gold(|| timed("gold", || gold(input))),
+    ))
 }
 
 fn main() -> anyhow::Result<()> {
     let input = read_input()?;
-
-    println!("Silver: {}", silver(input.as_str()));
-    println!("  Gold: {}", gold(input.as_str()));
+    run_repeated(&input, run)?.emit();
 
     Ok(())
 }
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lines_without_digits() {
+        let input = "1abc2\n\nabcdef\ntreb7uchet\n";
+        assert_eq!(silver(input), 12 + 77);
+    }
+
+    #[test]
+    fn manual_matches_aho_corasick() {
+        let input = "\
+two1nine
+eightwothree
+abcone2threexyz
+xtwone3four
+4nineeightseven2
+zoneight234
+7pqrstsixteen
+oneight
+twone
+";
+        let ac = AhoCorasick::new(GOLD_DIGITS.map(|(pattern, _)| pattern)).unwrap();
+
+        for line in input.lines() {
+            assert_eq!(solve_line_manual(line), solve_line(line, &ac, &GOLD_DIGITS, true), "{line}");
+        }
+        assert_eq!(solve_line_manual("oneight"), Some(18));
+        assert_eq!(solve_line_manual("abc"), None);
+        assert_eq!(gold(input), 281 + 18 + 21);
+    }
+
+    #[test]
+    fn custom_digit_spellings() {
+        let digits = [("yksi", 1), ("kaksi", 2)];
+        let input = "yksikaksi\nxkaksiyksix\nkaksi\n1\n";
+        assert_eq!(solve(input, &digits, true), 12 + 21 + 22);
+    }
+
+    #[test]
+    fn overlapping_first_last() {
+        let ac = AhoCorasick::new(GOLD_DIGITS.map(|(pattern, _)| pattern)).unwrap();
+
+        assert_eq!(solve_line("oneight", &ac, &GOLD_DIGITS, true), Some(18));
+        assert_eq!(solve_line("eightwothree", &ac, &GOLD_DIGITS, true), Some(83));
+        assert_eq!(solve_line("eightwo", &ac, &GOLD_DIGITS, true), Some(82));
+        assert_eq!(solve_line("seven", &ac, &GOLD_DIGITS, true), Some(77));
+        assert_eq!(solve_line("xsevenx", &ac, &GOLD_DIGITS, true), Some(77));
+    }
+
+    #[test]
+    fn non_overlapping_matches() {
+        let ac = AhoCorasick::new(GOLD_DIGITS.map(|(pattern, _)| pattern)).unwrap();
+
+        assert_eq!(solve_line("oneight", &ac, &GOLD_DIGITS, true), Some(18));
+        assert_eq!(solve_line("oneight", &ac, &GOLD_DIGITS, false), Some(11));
+        assert_eq!(solve_line("twone3", &ac, &GOLD_DIGITS, false), Some(23));
+        assert_eq!(solve("oneight\neightwo\n", &GOLD_DIGITS, false), 11 + 88);
+    }
+
+    #[test]
+    fn nested_patterns() {
+        // "b" is reported before "abc" even though "abc" starts earlier
+        let digits = [("abc", 1), ("b", 2), ("c", 3)];
+        let ac = AhoCorasick::new(digits.map(|(pattern, _)| pattern)).unwrap();
+
+        assert_eq!(solve_line("abc", &ac, &digits, true), Some(13));
+        assert_eq!(solve_line("xabcb", &ac, &digits, true), Some(12));
+    }
+}
@@ -1,13 +1,28 @@
 use std::str::FromStr;
 
-use crate::error::GameParseError;
+use super::error::GameParseError;
 
-pub type RGB = (u8, u8, u8);
+pub type Rgb = (u8, u8, u8);
+
+/// Amount of cubes of each color.
+#[derive(Debug, Clone, Copy)]
+pub struct CubeSet {
+    pub red: u8,
+    pub green: u8,
+    pub blue: u8,
+}
+
+impl CubeSet {
+    /// Check whether `set` could be drawn from these cubes.
+    pub fn contains(&self, set: Rgb) -> bool {
+        set.0 <= self.red && set.1 <= self.green && set.2 <= self.blue
+    }
+}
 
 #[derive(Debug)]
 pub struct Game {
     pub id: usize,
-    pub sets: Vec<RGB>,
+    pub sets: Vec<Rgb>,
 }
 
 impl FromStr for Game {
@@ -18,7 +33,7 @@ impl FromStr for Game {
         let (id, s) = s.split_once(": ").ok_or(GameParseError::MissingSemicolon)?;
         let id = id.parse::<usize>().map_err(|_| GameParseError::InvalidGameId)?;
 
-        let mut sets: Vec<RGB> = Vec::new();
+        let mut sets: Vec<Rgb> = Vec::new();
         for set in s.split(';') {
             let mut red = 0;
             let mut green = 0;
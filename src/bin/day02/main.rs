@@ -1,16 +1,24 @@
-use advent::read_input;
+use advent::{read_input, run_repeated, part_filter, Answers, timed};
 
 mod error;
 mod game;
 
-use game::Game;
+use game::{Game, CubeSet};
+
+/// Cubes in the bag for silver.
+const SILVER_LIMIT: CubeSet = CubeSet { red: 12, green: 13, blue: 14 };
 
 fn silver(input: &str) -> anyhow::Result<usize> {
+    silver_with_limit(input, SILVER_LIMIT)
+}
+
+/// Sum IDs of games which are possible with `limit` cubes in the bag.
+fn silver_with_limit(input: &str, limit: CubeSet) -> anyhow::Result<usize> {
     let mut possible_sum = 0;
     for line in input.trim().lines() {
         let game: Game = line.parse()?;
 
-        if game.sets.iter().any(|&set| set.0 > 12 || set.1 > 13 || set.2 > 14) {
+        if game.sets.iter().any(|&set| !limit.contains(set)) {
             //println!("Game {:3} is impossible", game.id);
             continue;
         }
@@ -47,11 +55,42 @@ fn gold(input: &str) -> anyhow::Result<usize> {
     Ok(power_sum)
 }
 
+/// Solve the puzzle for given input.
+pub fn run(input: &str) -> anyhow::Result<Answers> {
+    let part = part_filter();
+
+    Ok(Answers::new(
+        part.silver(|| timed("silver", || silver(input))).transpose()?,
+        part.gold(|| timed("gold", || gold(input))).transpose()?,
+    ))
+}
+
 fn main() -> anyhow::Result<()> {
     let input = read_input()?;
-
-    println!("Silver: {}", silver(input.as_str())?);
-    println!("  Gold: {}", gold(input.as_str())?);
+    run_repeated(&input, run)?.emit();
 
     Ok(())
 }
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+Game 1: 3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green
+Game 2: 1 blue, 2 green; 3 green, 4 blue, 1 red; 1 green, 1 blue
+Game 3: 8 green, 6 blue, 20 red; 5 blue, 4 red, 13 green; 5 green, 1 red
+Game 4: 1 green, 3 red, 6 blue; 3 green, 6 red; 3 green, 15 blue, 14 red
+Game 5: 6 red, 1 blue, 3 green; 2 blue, 1 red, 2 green
+";
+
+    #[test]
+    fn stricter_limit() {
+        assert_eq!(silver(EXAMPLE).unwrap(), 1 + 2 + 5);
+
+        // Game 1 draws 6 blue cubes at once
+        let strict = CubeSet { red: 12, green: 13, blue: 5 };
+        assert_eq!(silver_with_limit(EXAMPLE, strict).unwrap(), 2 + 5);
+    }
+}
@@ -2,29 +2,17 @@ use std::collections::{HashMap, HashSet};
 
 use grid::Grid;
 
-use advent::read_input;
-
-const DIRS: [(i8, i8); 8] = [
-    (-1,  0),
-    ( 1,  0),
-    ( 0, -1),
-    ( 0,  1),
-    // Diagonals
-    (-1,  1),
-    (-1, -1),
-    ( 1,  1),
-    ( 1, -1),
-];
-
-fn grid_from_string(mut s: String) -> Grid<u8> {
+use advent::{read_input, run_repeated, neighbors8, part_filter, maybe_dump, Answers, timed};
+
+fn grid_from_string(s: &str) -> Grid<u8> {
     // First, calculate number of columns (line length)
-    let cols = s.lines().nth(0).map(|line| line.len()).unwrap();
+    let cols = s.lines().next().map(|line| line.len()).unwrap();
 
-    // Remove all newlines from the original string,
+    // Leave out all newlines from the original string,
     // this ensures that we can convert the string into 1D array of bytes.
-    s.retain(|c| !c.is_ascii_whitespace());
+    let bytes = s.bytes().filter(|c| !c.is_ascii_whitespace()).collect();
 
-    Grid::from_vec(s.into_bytes(), cols)
+    Grid::from_vec(bytes, cols)
 }
 
 #[derive(Debug)]
@@ -63,9 +51,35 @@ impl Number {
     }
 }
 
+/// Solve the puzzle for given input.
+pub fn run(input: &str) -> anyhow::Result<Answers> {
+    let grid = timed("parse", || grid_from_string(input));
+    if maybe_dump(&grid, |&byte| byte as char)? {
+        return Ok(Answers::default());
+    }
+    let (silver_sum, gold_sum) = timed("solve", || solve(&grid, true));
+
+    // Both parts are solved in the same scan, only printing can be skipped
+    let part = part_filter();
+
+    Ok(Answers::new(
+        part.silver(|| silver_sum),
+        part.gold(|| gold_sum),
+    ))
+}
+
 fn main() -> anyhow::Result<()> {
     let input = read_input()?;
-    let grid = grid_from_string(input);
+    run_repeated(&input, run)?.emit();
+
+    Ok(())
+}
+
+/// Solve both parts in a single scan over the grid.
+///
+/// Symbols connect to numbers orthogonally, and also diagonally if
+/// `diagonals` is set, as in the puzzle. Returns `(silver, gold)` sums.
+fn solve(grid: &Grid<u8>, diagonals: bool) -> (usize, usize) {
     let (rows, cols) = grid.size();
 
     let mut silver_sum: usize = 0;
@@ -109,21 +123,9 @@ fn main() -> anyhow::Result<()> {
 
             // Then, look to all directions to check if it is connected to any
             // symbols.
-            for (dx, dy) in DIRS {
-                // Bounds checks, usize overflows
-                let Some(nx) = x.checked_add_signed(dx as isize) else {
-                    continue;
-                };
-
-                let Some(ny) = y.checked_add_signed(dy as isize) else {
-                    continue;
-                };
-
-                // Grid bounds
-                let Some(&s) = grid.get(nx, ny) else {
-                    continue;
-                };
-
+            let neighbors = neighbors8(grid, x, y)
+                .filter(|&(nx, ny, _)| diagonals || nx == x || ny == y);
+            for (nx, ny, &s) in neighbors {
                 if !s.is_ascii_digit() && s != b'.' {
                     // This digit is connected to a symbol.
                     // Set is required here as same number may be connected to the same symbol multiple times.
@@ -153,8 +155,35 @@ fn main() -> anyhow::Result<()> {
             }
         ).sum();
 
-    println!("Silver: {}", silver_sum);
-    println!("  Gold: {}", gold_sum);
+    (silver_sum, gold_sum)
+}
 
-    Ok(())
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diagonal_adjacency() {
+        // `*` touches both numbers only diagonally
+        let grid = grid_from_string("12....\n..*...\n...34.\n");
+
+        assert_eq!(solve(&grid, true), (12 + 34, 12 * 34));
+        assert_eq!(solve(&grid, false), (0, 0));
+    }
+
+    #[test]
+    fn gears_sharing_a_number() {
+        // `12` touches two distinct gears, each counts it once
+        let grid = grid_from_string("3*12*5.\n.......\n");
+        assert_eq!(solve(&grid, true).1, 3 * 12 + 12 * 5);
+
+        // `123` touches the same gear from all of its digits
+        let grid = grid_from_string(".123.\n..*..\n.4...\n");
+        assert_eq!(solve(&grid, true).1, 123 * 4);
+
+        // A lone number touching a gear twice is not a pair
+        let grid = grid_from_string(".12.\n.*..\n....\n");
+        assert_eq!(solve(&grid, true).1, 0);
+    }
 }
@@ -1,4 +1,7 @@
-use advent::read_input;
+use std::io;
+
+use advent::{read_input, read_input_lines, repeat_count, run_repeated, part_filter, Answers, timed};
+use anyhow::anyhow;
 
 /// Bitset for storing integers between 0-127
 #[derive(Debug)]
@@ -29,42 +32,62 @@ impl NumberBitSet {
     }
 }
 
+/// Scratchcard reduced to its id and the amount of matching numbers.
+#[derive(Debug, Clone, Copy)]
+struct Card {
+    id: usize,
+    matching: usize,
+}
+
 #[derive(Debug)]
 struct MatchCountIter<T>(T);
 impl<T> MatchCountIter<T> {
     fn from_linesource(linesource: T) -> Self { Self(linesource) }
 }
 
-impl<'a, T> Iterator for MatchCountIter<T>
+impl<T> Iterator for MatchCountIter<T>
 where
-    T: Iterator<Item = &'a str>    
+    T: Iterator<Item = io::Result<String>>
 {
-    type Item = usize;
+    type Item = anyhow::Result<Card>;
     fn next(&mut self) -> Option<Self::Item> {
-        if let Some(line) = self.0.next() {
-            let (_, rest) = line.split_once(':')?;
-            let (winning, have) = rest.split_once('|')?;
-
-            // Only extend winning set, as we can check for membership separately.
-            // Note: Set is empty, but the same allocation is reused in order
-            //       to avoid reallocating on each time next() is called.
-            let winning = NumberBitSet::from_iter(
-                winning.split_ascii_whitespace()
-                    .map(|num| num.parse::<u8>().unwrap())
-            );
-
-            let have = NumberBitSet::from_iter(
-                have.split_ascii_whitespace()
-                    .map(|num| num.parse::<u8>().unwrap())
-            );
-
-            Some(winning.count_matching_numbers(&have) as usize)
-        } else {
-            None
-        }
+        // Blank lines, e.g. around the cards, are skipped
+        let line = loop {
+            match self.0.next()? {
+                Ok(line) if line.trim().is_empty() => continue,
+                Ok(line) => break line,
+                Err(err) => return Some(Err(err.into())),
+            }
+        };
+
+        Some(parse_card(&line))
     }
 }
 
+/// Parse a card from `Card N: winning numbers | numbers we have`.
+fn parse_card(line: &str) -> anyhow::Result<Card> {
+    let (header, rest) = line.split_once(':')
+        .ok_or_else(|| anyhow!("missing ':' in card '{line}'"))?;
+    let id = header.strip_prefix("Card")
+        .and_then(|id| id.trim().parse().ok())
+        .ok_or_else(|| anyhow!("invalid card header '{header}'"))?;
+    let (winning, have) = rest.split_once('|')
+        .ok_or_else(|| anyhow!("missing '|' in card '{line}'"))?;
+
+    // Only extend winning set, as we can check for membership separately.
+    let winning = NumberBitSet::from_iter(
+        winning.split_ascii_whitespace()
+            .map(|num| num.parse::<u8>().unwrap())
+    );
+
+    let have = NumberBitSet::from_iter(
+        have.split_ascii_whitespace()
+            .map(|num| num.parse::<u8>().unwrap())
+    );
+
+    Ok(Card { id, matching: winning.count_matching_numbers(&have) as usize })
+}
+
 fn score(match_count: usize) -> usize {
     if match_count > 0 {
         2_usize.pow(match_count as u32 - 1)
@@ -73,34 +96,140 @@ fn score(match_count: usize) -> usize {
     }
 }
 
-fn silver(input: &str) -> usize {
-    MatchCountIter::from_linesource(input.trim().lines())
-        .map(|match_count| score(match_count))
+fn silver(cards: &[Card]) -> usize {
+    cards.iter()
+        .map(|card| score(card.matching))
         .sum()
 }
 
-fn gold(input: &str) -> usize {
-    let matching = MatchCountIter::from_linesource(input.trim().lines())
-        .collect::<Vec<_>>();
-    
+/// Final amount of each card after all won copies have been handed out.
+///
+/// Cards never win copies past the last card.
+fn card_counts(matching: &[usize]) -> Vec<usize> {
     let mut card_counts = vec![1_usize; matching.len()];
     for i in 0..card_counts.len() {
         let count = card_counts[i];
 
         // Accumulate extra cards if card has matching numbers
-        for extra_i in 0..matching[i] {
-            card_counts[i+extra_i+1] += count;
+        let last = (i + matching[i]).min(card_counts.len() - 1);
+        for extra in &mut card_counts[i+1..=last] {
+            *extra += count;
         }
     }
 
-    card_counts.iter().sum()
+    card_counts
 }
 
-fn main() -> anyhow::Result<()> {
-    let input = read_input()?;
+/// Match counts of `cards`, indexed by card position.
+///
+/// Returns an error unless cards are numbered `1..=n` in order,
+/// as copies are won by position.
+fn matching_in_order(cards: &[Card]) -> anyhow::Result<Vec<usize>> {
+    cards.iter().zip(1..)
+        .map(|(card, expected)| if card.id == expected {
+            Ok(card.matching)
+        } else {
+            Err(anyhow!("found card {} where card {expected} was expected", card.id))
+        })
+        .collect()
+}
+
+fn gold(cards: &[Card]) -> anyhow::Result<usize> {
+    Ok(card_counts(&matching_in_order(cards)?).iter().sum())
+}
+
+/// Solve the puzzle for given input.
+pub fn run(input: &str) -> anyhow::Result<Answers> {
+    run_lines(input.lines().map(|line| Ok(line.to_string())))
+}
 
-    println!("Silver: {}", silver(&input));
-    println!("  Gold: {}", gold(&input));
+/// Like [`run`], but cards are streamed from given lines.
+fn run_lines(lines: impl Iterator<Item = io::Result<String>>) -> anyhow::Result<Answers> {
+    // Only ids and match counts of the cards are kept around
+    let cards = timed("parse", || {
+        MatchCountIter::from_linesource(lines).collect::<anyhow::Result<Vec<_>>>()
+    })?;
+
+    let part = part_filter();
+
+    Ok(Answers::new(
+        part.silver(|| timed("silver", || silver(&cards))),
+        part.gold(|| timed("gold", || gold(&cards))).transpose()?,
+    ))
+}
+
+fn main() -> anyhow::Result<()> {
+    // Cards are streamed from the input, unless it's needed for several runs
+    let answers = match repeat_count() {
+        Some(_) => run_repeated(&read_input()?, run)?,
+        None => run_lines(read_input_lines()?)?,
+    };
+    answers.emit();
 
     Ok(())
 }
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Cards with given ids and match counts.
+    fn cards(cards: &[(usize, usize)]) -> Vec<Card> {
+        cards.iter().map(|&(id, matching)| Card { id, matching }).collect()
+    }
+
+    #[test]
+    fn example_card_counts() {
+        let matching = [4, 2, 2, 1, 0, 0];
+        assert_eq!(card_counts(&matching), &[1, 2, 4, 8, 14, 1]);
+        assert_eq!(gold(&cards(&[(1, 4), (2, 2), (3, 2), (4, 1), (5, 0), (6, 0)])).unwrap(), 30);
+    }
+
+    #[test]
+    fn parse_card_ids() {
+        let lines = ["Card   1: 41 48 | 48 41 9", "Card  12: 1 2 | 3 4"].map(|line| Ok(line.to_string()));
+        let parsed: Vec<(usize, usize)> = MatchCountIter::from_linesource(lines.into_iter())
+            .map(|card| card.map(|card| (card.id, card.matching)))
+            .collect::<anyhow::Result<_>>()
+            .unwrap();
+        assert_eq!(parsed, &[(1, 2), (12, 0)]);
+    }
+
+    #[test]
+    fn out_of_order_cards() {
+        let err = gold(&cards(&[(1, 1), (3, 0), (2, 0)])).unwrap_err();
+        assert_eq!(err.to_string(), "found card 3 where card 2 was expected");
+
+        // Silver doesn't care about the order
+        assert_eq!(silver(&cards(&[(2, 1), (1, 2)])), 3);
+    }
+
+    #[test]
+    fn copies_stop_at_last_card() {
+        assert_eq!(card_counts(&[0, 3, 1]), &[1, 1, 2]);
+        assert_eq!(card_counts(&[5]), &[1]);
+        assert_eq!(card_counts(&[]), &[] as &[usize]);
+    }
+
+    #[test]
+    fn blank_lines_and_missing_separators() {
+        let example = "
+Card 1: 41 48 83 86 17 | 83 86  6 31 17  9 48 53
+Card 2: 13 32 20 16 61 | 61 30 68 82 17 32 24 19
+Card 3:  1 21 53 59 44 | 69 82 63 72 16 21 14  1
+Card 4: 41 92 73 84 69 | 59 84 76 51 58  5 54 83
+Card 5: 87 83 26 28 32 | 88 30 70 12 93 22 82 36
+Card 6: 31 18 13 56 72 | 74 77 10 23 35 67 36 11
+
+";
+        let answers = run(example).unwrap();
+        assert_eq!(answers.silver.as_deref(), Some("13"));
+        assert_eq!(answers.gold.as_deref(), Some("30"));
+
+        let err = run("Card 1: 41 48 | 83 86\nCard 2 13 32 | 61 30\n").unwrap_err();
+        assert_eq!(err.to_string(), "missing ':' in card 'Card 2 13 32 | 61 30'");
+        let err = run("Card 1: 41 48 83 86\n").unwrap_err();
+        assert_eq!(err.to_string(), "missing '|' in card 'Card 1: 41 48 83 86'");
+    }
+}
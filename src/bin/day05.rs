@@ -1,16 +1,59 @@
-use std::{ops::Range, str::FromStr};
+use std::{ops::Range, str::FromStr, collections::HashSet};
 
-use advent::read_input;
+use advent::{read_input, run_repeated, part_filter, Answers, dbgln, timed};
 use anyhow::anyhow;
 
-#[derive(Debug)]
-struct Map(Vec<MapLine>);
+#[derive(Debug, PartialEq)]
+struct Map {
+    /// Category this map translates from, e.g. `seed`
+    from: String,
+    /// Category this map translates to, e.g. `soil`
+    to: String,
+    lines: Vec<MapLine>,
+}
+
 impl Map {
     /// Translate given ranges.
-    fn translate(&self, mut seed_ranges: Vec<Range<usize>>) -> Vec<Range<usize>> {
+    #[cfg(feature = "parallel")]
+    fn translate(&self, seed_ranges: Vec<Range<u64>>) -> anyhow::Result<Vec<Range<u64>>> {
+        self.translate_parallel(seed_ranges)
+    }
+
+    /// Translate given ranges.
+    #[cfg(not(feature = "parallel"))]
+    fn translate(&self, seed_ranges: Vec<Range<u64>>) -> anyhow::Result<Vec<Range<u64>>> {
+        self.translate_serial(seed_ranges)
+    }
+
+    /// Translate given ranges one after another.
+    #[cfg(any(test, not(feature = "parallel")))]
+    fn translate_serial(&self, seed_ranges: Vec<Range<u64>>) -> anyhow::Result<Vec<Range<u64>>> {
+        let mut out = Vec::with_capacity(seed_ranges.len());
+        for range in seed_ranges {
+            out.extend(self.translate_range(range)?);
+        }
+        Ok(out)
+    }
+
+    /// Translate given ranges concurrently.
+    /// Ranges don't interact within a single map, so each one is handled independently.
+    #[cfg(feature = "parallel")]
+    fn translate_parallel(&self, seed_ranges: Vec<Range<u64>>) -> anyhow::Result<Vec<Range<u64>>> {
+        use rayon::prelude::*;
+        let translated = seed_ranges.into_par_iter()
+            .map(|range| self.translate_range(range))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        Ok(translated.into_iter().flatten().collect())
+    }
+
+    /// Translate a single range, which may be split into several ones.
+    fn translate_range(&self, range: Range<u64>) -> anyhow::Result<Vec<Range<u64>>> {
+        // Stack of ranges left to translate, split off parts are pushed back here.
+        let mut seed_ranges = vec![range];
+
         // Stack of translated ranges.
         // This will become `seed_ranges` for next iteration.
-        let mut out_ranges: Vec<Range<usize>> = Vec::new();
+        let mut out_ranges: Vec<Range<u64>> = Vec::new();
         
         while let Some(seeds) = seed_ranges.pop() {
             // println!("--- SEED START ---");
@@ -18,13 +61,13 @@ impl Map {
             // Marker to indicate whether some part of this range was translated.
             let mut was_translated = false;
 
-            for translator in &self.0 {
+            for translator in &self.lines {
                 if was_translated {
                     // Seed range was already translated, move on...
                     break;
                 }
 
-                if let Some(changed) = translator.try_translate(&seeds) {
+                if let Some(changed) = translator.try_translate(&seeds)? {
                     // `changed` now contains at LEAST one range that was
                     // translated using this translator (indicated in `ready` flag).
                     //
@@ -54,159 +97,218 @@ impl Map {
             // println!("--- SEED END   ---\n");
         }
 
-        out_ranges
+        Ok(out_ranges)
     }
 
-    fn from_lines<'a, T>(source: &mut T) -> Option<Self>
+    /// Parse a single map block, including its header line.
+    ///
+    /// Returns `Ok(None)` if there are no more blocks left.
+    fn from_lines<'a, T>(source: &mut T) -> anyhow::Result<Option<Self>>
     where
         T: Iterator<Item = &'a str>
     {
+        // Skip any empty lines between blocks
+        let Some(header) = source.by_ref().find(|line| !line.is_empty()) else {
+            return Ok(None);
+        };
+
+        // Header has format `<from>-to-<to> map:`
+        let (from, to) = header.strip_suffix(" map:")
+            .and_then(|categories| categories.split_once("-to-"))
+            .ok_or_else(|| anyhow!("invalid map header '{header}'"))?;
+
         let mut lines: Vec<MapLine> = Vec::new();
-        while let Some(line) = source.next() {
+        for line in source.by_ref() {
             // Encountered possible newline
             if line.is_empty() {
-                return Some(Self(lines));
-            }
-
-            if line.starts_with(char::is_alphabetic) {
-                continue;
+                break;
             }
 
-            lines.push(line.parse::<MapLine>().unwrap());
+            lines.push(line.parse::<MapLine>()?);
         }
 
-        if lines.is_empty() {
-            None
-        } else {
-            Some(Self(lines))
+        Ok(Some(Self { from: from.to_string(), to: to.to_string(), lines }))
+    }
+}
+
+/// Order maps into a translation chain by following their categories,
+/// starting from `seed`.
+///
+/// Returns an error if some map can't be reached from `seed`
+/// or if the chain would visit some category twice.
+fn chain_maps(mut maps: Vec<Map>) -> anyhow::Result<Vec<Map>> {
+    let mut ordered: Vec<Map> = Vec::with_capacity(maps.len());
+    let mut category = "seed".to_string();
+    let mut visited = HashSet::from([category.clone()]);
+
+    while !maps.is_empty() {
+        let next = maps.iter()
+            .position(|map| map.from == category)
+            .ok_or_else(|| anyhow!("broken map chain, no map from category '{category}'"))?;
+        let map = maps.swap_remove(next);
+
+        if !visited.insert(map.to.clone()) {
+            return Err(anyhow!("cyclic map chain, category '{}' is visited twice", map.to));
         }
+
+        category.clone_from(&map.to);
+        ordered.push(map);
     }
 
+    Ok(ordered)
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 struct MapLine {
-    dst: Range<usize>,
-    src: Range<usize>,
+    dst: Range<u64>,
+    src: Range<u64>,
 }
 
-fn adjust_range(range: Range<usize>, offset: isize) -> Range<usize> {
-    if offset >= 0 {
-        let offset = offset as usize;
-        range.start+offset..range.end+offset
-    } else {
-        range.start.checked_add_signed(offset).expect("seed.start overflowed")
-        ..
-        range.end.checked_add_signed(offset).expect("seed.end overflowed")
-    }
+/// Move `range` by `offset`, returning an error if it would leave `u64`.
+fn adjust_range(range: Range<u64>, offset: i64) -> anyhow::Result<Range<u64>> {
+    debug_assert!(range.start <= range.end, "reversed range {range:?}");
+    range.start.checked_add_signed(offset)
+        .zip(range.end.checked_add_signed(offset))
+        .map(|(start, end)| start..end)
+        .ok_or_else(|| anyhow!("moving range {range:?} by {offset} overflows"))
 }
 
-impl MapLine {
-    /// Try to translate given range.
-    fn try_translate(&self, seeds: &Range<usize>) -> Option<Vec<(Range<usize>, bool)>> {
-        if seeds.end <= self.src.start || seeds.start >= self.src.end {
-            // println!("  1-to-1 seeds: {:?} - range: {:?}", seeds, self.src);
-            return None
-        }
+/// Parts of a range split by another one, see [`split_range`].
+type SplitRange = (Option<Range<u64>>, Option<Range<u64>>, Option<Range<u64>>);
 
-        // Seeds overlap in some way
-        // Calculate offset for adjusting
-        let offset = self.dst.start as isize - self.src.start as isize;
-
-        // If seeds are fully contained within the area, we can just map them
-        // without splitting the range
-        if self.src.start <= seeds.start && self.src.end >= seeds.end {
-            // println!("  contained overlap seeds: {:?} - range: {:?}", seeds, self.src);
-            return Some(vec![
-                (adjust_range(seeds.clone(), offset), true)
-            ])
-        }
+/// Ranges produced by [`MapLine::try_translate`], flagged whether they were translated.
+type Translated = Vec<(Range<u64>, bool)>;
 
-        // Seeds are larger than the area, needs to be split 3-way
-        if seeds.start < self.src.start && seeds.end > self.src.end {
-            let left_outside = seeds.start..self.src.start;
-            let inside = self.src.start..self.src.end;
-            let right_outside = self.src.end..seeds.end;
-            println!("  FULLY OUTSIDE seeds: {:?} - range: {:?}:", seeds, self.src);
-            println!("    inside (map): {:?}, outside (left, pass): {:?}, outside (right, pass): {:?}", inside, left_outside, right_outside);
-            // panic!("impossible!");
-            return Some(vec![
-                (left_outside, false),
-                (adjust_range(inside, offset), true),
-                (right_outside, false),
-            ]);
-        }
+/// Split `seeds` into parts `(left_outside, inside, right_outside)` of `by`.
+/// Parts that would be empty are [`None`].
+fn split_range(seeds: &Range<u64>, by: &Range<u64>) -> SplitRange {
+    let non_empty = |range: Range<u64>| (range.start < range.end).then_some(range);
 
-        // Partial overlaps, 2-way splits
-        // Left side out of the range
-        if seeds.end > self.src.start && seeds.start < self.src.start {
-            let inside = self.src.start..seeds.end;
-            let outside = seeds.start..self.src.start;
-            println!("  LEFT  OUTSIDE seeds: {:?} - range: {:?}:", seeds, self.src);
-            println!("    inside (map): {:?}, outside (pass): {:?}", inside, outside);
-
-            return Some(vec![
-                (adjust_range(inside, offset), true),
-                (outside, false)
-            ]);
-        }
+    (
+        non_empty(seeds.start..seeds.end.min(by.start)),
+        non_empty(seeds.start.max(by.start)..seeds.end.min(by.end)),
+        non_empty(seeds.start.max(by.end)..seeds.end),
+    )
+}
 
-        // Right side out of the range
-        if seeds.start < self.src.end && seeds.end > self.src.end {
-            let outside = self.src.end..seeds.end;
-            let inside = seeds.start..self.src.end;
-            println!("  RIGHT OUTSIDE seeds: {:?} - range: {:?}:", seeds, self.src);
-            println!("    inside (map): {:?}, outside (pass): {:?}", inside, outside);
-
-            return Some(vec![
-                (adjust_range(inside, offset), true),
-                (outside, false)
-            ]);
-        }
+impl MapLine {
+    /// Try to translate given range.
+    ///
+    /// Returns [`None`] if the range doesn't overlap with this line. Otherwise
+    /// the overlapping part is translated (`true`) and parts outside of this
+    /// line are split off (`false`).
+    fn try_translate(&self, seeds: &Range<u64>) -> anyhow::Result<Option<Translated>> {
+        let (left_outside, inside, right_outside) = split_range(seeds, &self.src);
+        let Some(inside) = inside else {
+            return Ok(None);
+        };
+
+        dbgln!("  seeds: {:?} - range: {:?}:", seeds, self.src);
+        dbgln!("    inside (map): {:?}, outside (left, pass): {:?}, outside (right, pass): {:?}", inside, left_outside, right_outside);
+
+        let mut out = vec![(adjust_range(inside, self.offset()?)?, true)];
+        out.extend(left_outside.into_iter().chain(right_outside).map(|range| (range, false)));
+        Ok(Some(out))
+    }
 
-        println!("!!!! unhandled overlap seeds: {:?} - range: {:?} !!!!", seeds, self.src);
-        unreachable!();
+    /// Distance from source to destination range.
+    fn offset(&self) -> anyhow::Result<i64> {
+        let (dst, src) = (self.dst.start, self.src.start);
+        let offset = if dst >= src {
+            i64::try_from(dst - src).ok()
+        } else {
+            i64::try_from(src - dst).ok().map(|offset| -offset)
+        };
+        offset.ok_or_else(|| anyhow!("offset from {src} to {dst} doesn't fit in i64"))
     }
 }
 
 
-fn solve(seeds: Seeds, maps: &[Map]) -> usize {
-    println!("starting seeds: {seeds:?}");
-    let pre_total = seeds.0.iter().fold(0, |acc, range| acc + range.len()) as isize;
-    // println!("pre total seeds:    {}", pre_total);
+fn solve(seeds: Seeds, maps: &[Map]) -> anyhow::Result<u64> {
+    dbgln!("starting seeds: {seeds:?}");
 
     let mut seeds = seeds.0;
     for map in maps {
-        seeds = map.translate(seeds);
-        let count = seeds.iter().fold(0, |acc, range| acc + range.len()) as isize;
-        println!("seeds after map: {:?} (count: {count}\n\n\n", &seeds);
+        let translated = map.translate(seeds.clone())?;
+
+        // Checked before coalescing, as merging overlapping ranges shrinks the total.
+        let count = check_seed_count(map, &seeds, &translated)?;
+
+        seeds = translated;
+        coalesce(&mut seeds);
+        dbgln!("seeds after map: {:?} (count: {count}\n\n\n", &seeds);
     }
 
+    assert!(seeds.iter().all(|range| range.start < range.end), "translated seed ranges must be non-empty");
+    seeds.iter().map(|range| range.start).min().ok_or_else(|| anyhow!("no seeds to translate"))
+}
 
-    let post_total = seeds.iter().fold(0, |acc, range| acc + range.len()) as isize;
-    if pre_total != post_total {
-        panic!("unexpected amount of seeds!, expected {pre_total} got {post_total} (diff: {})", post_total - pre_total);
+/// Check that translating `before` with `map` into `after` neither lost nor invented seeds.
+///
+/// Returns the amount of seeds.
+fn check_seed_count(map: &Map, before: &[Range<u64>], after: &[Range<u64>]) -> anyhow::Result<u64> {
+    let count = |ranges: &[Range<u64>]| ranges.iter().map(|range| range.end - range.start).sum::<u64>();
+    let (before_total, after_total) = (count(before), count(after));
+
+    if before_total != after_total {
+        return Err(anyhow!(
+            "{}-to-{} map changed amount of seeds, {before_total} {} before and {after_total} {} after",
+            map.from, map.to, map.from, map.to,
+        ));
     }
-    seeds.iter().min_by_key(|&range| range.start.min(range.end)).unwrap().start
+
+    Ok(after_total)
 }
 
-fn main() -> anyhow::Result<()> {
-    let input = read_input()?;
+/// Merge touching and overlapping ranges, leaving `ranges` sorted by start.
+/// Empty ranges are dropped.
+fn coalesce(ranges: &mut Vec<Range<u64>>) {
+    ranges.retain(|range| !range.is_empty());
+    ranges.sort_unstable_by_key(|range| range.start);
+
+    let mut merged: Vec<Range<u64>> = Vec::with_capacity(ranges.len());
+    for range in ranges.drain(..) {
+        match merged.last_mut() {
+            Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+            _ => merged.push(range),
+        }
+    }
+
+    *ranges = merged;
+}
 
+/// Parse silver seeds, gold seeds and maps ordered into a translation chain.
+fn parse(input: &str) -> anyhow::Result<(Seeds, Seeds, Vec<Map>)> {
     let mut lines = input.lines();
-    let seedline = lines.next().unwrap();
+    let seedline = lines.next().ok_or_else(|| anyhow!("missing seeds"))?;
     let _ = lines.next(); // Eat newline after seeds
 
-    let silver_seeds = Seeds::from_singles_str(seedline);
-    let gold_seeds = Seeds::from_ranges_str(seedline);
+    let silver_seeds = Seeds::from_singles_str(seedline)?;
+    let gold_seeds = Seeds::from_ranges_str(seedline)?;
 
     let mut maps: Vec<Map> = Vec::new();
-    while let Some(map) = Map::from_lines(&mut lines) {
+    while let Some(map) = Map::from_lines(&mut lines)? {
         maps.push(map)
     }
 
-    println!("Silver: {}", solve(silver_seeds, &maps));
-    println!("  Gold: {}", solve(gold_seeds, &maps));
+    Ok((silver_seeds, gold_seeds, chain_maps(maps)?))
+}
+
+/// Solve the puzzle for given input.
+pub fn run(input: &str) -> anyhow::Result<Answers> {
+    let (silver_seeds, gold_seeds, maps) = timed("parse", || parse(input))?;
+
+    let part = part_filter();
+
+    Ok(Answers::new(
+        part.silver(|| timed("silver", || solve(silver_seeds, &maps))).transpose()?,
+        part.gold(|| timed("gold", || solve(gold_seeds, &maps))).transpose()?,
+    ))
+}
+
+fn main() -> anyhow::Result<()> {
+    let input = read_input()?;
+    run_repeated(&input, run)?.emit();
 
     Ok(())
 }
@@ -216,6 +318,190 @@ fn main() -> anyhow::Result<()> {
 mod tests {
     use super::*;
 
+    const EXAMPLE: &str = "\
+seeds: 79 14 55 13
+
+seed-to-soil map:
+50 98 2
+52 50 48
+
+soil-to-fertilizer map:
+0 15 37
+37 52 2
+39 0 15
+
+fertilizer-to-water map:
+49 53 8
+0 11 42
+42 0 7
+57 7 4
+
+water-to-light map:
+88 18 7
+18 25 70
+
+light-to-temperature map:
+45 77 23
+81 45 19
+68 64 13
+
+temperature-to-humidity map:
+0 69 1
+1 0 69
+
+humidity-to-location map:
+60 56 37
+56 93 4
+";
+
+    /// Example input with its map blocks in reverse order
+    fn shuffled_example() -> String {
+        let mut blocks: Vec<&str> = EXAMPLE.trim().split("\n\n").collect();
+        blocks[1..].reverse();
+        blocks.join("\n\n")
+    }
+
+    #[test]
+    fn maps_in_any_order() {
+        let (silver_seeds, gold_seeds, maps) = parse(&shuffled_example()).unwrap();
+
+        let categories: Vec<&str> = maps.iter().map(|map| map.from.as_str()).collect();
+        assert_eq!(categories, &["seed", "soil", "fertilizer", "water", "light", "temperature", "humidity"]);
+        assert_eq!(solve(silver_seeds, &maps).unwrap(), 35);
+        assert_eq!(solve(gold_seeds, &maps).unwrap(), 46);
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn parallel_matches_serial() {
+        let (_, gold_seeds, maps) = parse(EXAMPLE).unwrap();
+
+        let mut serial = gold_seeds.0.clone();
+        let mut parallel = gold_seeds.0;
+        for map in &maps {
+            serial = map.translate_serial(serial).unwrap();
+            parallel = map.translate_parallel(parallel).unwrap();
+
+            let mut sorted_serial = serial.clone();
+            let mut sorted_parallel = parallel.clone();
+            sorted_serial.sort_by_key(|range| (range.start, range.end));
+            sorted_parallel.sort_by_key(|range| (range.start, range.end));
+            assert_eq!(sorted_serial, sorted_parallel, "{}-to-{}", map.from, map.to);
+        }
+    }
+
+    #[test]
+    fn map_display_roundtrip() {
+        let block = "soil-to-fertilizer map:\n0 15 37\n37 52 2\n39 0 15\n";
+        let map = Map::from_lines(&mut block.lines()).unwrap().unwrap();
+
+        let displayed = map.to_string();
+        assert_eq!(displayed, block);
+        assert_eq!(Map::from_lines(&mut displayed.lines()).unwrap().unwrap(), map);
+    }
+
+    #[test]
+    fn example_lowest_location() {
+        let (silver_seeds, gold_seeds, maps) = parse(EXAMPLE).unwrap();
+        assert_eq!(solve(silver_seeds, &maps).unwrap(), 35);
+        assert_eq!(solve(gold_seeds, &maps).unwrap(), 46);
+    }
+
+    #[test]
+    fn lossy_translation_is_an_error() {
+        let block = "seed-to-soil map:\n50 98 2\n";
+        let map = Map::from_lines(&mut block.lines()).unwrap().unwrap();
+
+        let before = [0..5, 90..100];
+        assert_eq!(check_seed_count(&map, &before, &map.translate(before.to_vec()).unwrap()).unwrap(), 15);
+
+        // Translation which dropped the two seeds mapped to soil 50..52
+        let err = check_seed_count(&map, &before, &[0..5, 90..98]).unwrap_err();
+        assert_eq!(err.to_string(), "seed-to-soil map changed amount of seeds, 15 seed before and 13 soil after");
+    }
+
+    #[test]
+    fn split_range_overlaps() {
+        let by = 10..20;
+        let cases = [
+            // Disjoint and touching
+            (0..5,   (Some(0..5), None, None)),
+            (0..10,  (Some(0..10), None, None)),
+            (20..25, (None, None, Some(20..25))),
+            (25..30, (None, None, Some(25..30))),
+            // Partial overlaps
+            (5..15,  (Some(5..10), Some(10..15), None)),
+            (15..25, (None, Some(15..20), Some(20..25))),
+            (9..11,  (Some(9..10), Some(10..11), None)),
+            (19..21, (None, Some(19..20), Some(20..21))),
+            // Contained
+            (10..20, (None, Some(10..20), None)),
+            (10..15, (None, Some(10..15), None)),
+            (15..20, (None, Some(15..20), None)),
+            (12..18, (None, Some(12..18), None)),
+            // Covering
+            (5..25,  (Some(5..10), Some(10..20), Some(20..25))),
+            (10..25, (None, Some(10..20), Some(20..25))),
+            (5..20,  (Some(5..10), Some(10..20), None)),
+        ];
+
+        for (seeds, expected) in cases {
+            assert_eq!(split_range(&seeds, &by), expected, "{seeds:?}");
+        }
+    }
+
+    #[test]
+    fn translate_splits_off_outside() {
+        let line: MapLine = "50 10 10".parse().unwrap();
+
+        assert_eq!(line.try_translate(&(0..10)).unwrap(), None);
+        assert_eq!(line.try_translate(&(20..30)).unwrap(), None);
+        assert_eq!(line.try_translate(&(12..15)).unwrap(), Some(vec![(52..55, true)]));
+        assert_eq!(
+            line.try_translate(&(5..25)).unwrap(),
+            Some(vec![(50..60, true), (5..10, false), (20..25, false)]),
+        );
+    }
+
+    #[test]
+    fn ranges_past_usize() {
+        // Values past 32 bits work the same on any platform
+        let line: MapLine = "0 10000000000 10".parse().unwrap();
+        assert_eq!(line.offset().unwrap(), -10_000_000_000);
+        assert_eq!(line.try_translate(&(10_000_000_005..10_000_000_020)).unwrap(),
+            Some(vec![(5..10, true), (10_000_000_010..10_000_000_020, false)]));
+
+        assert!("0 18446744073709551615 2".parse::<MapLine>().unwrap_err().to_string().contains("overflows"));
+        assert!(Seeds::from_ranges_str("seeds: 18446744073709551615 2").unwrap_err().to_string().contains("overflows"));
+
+        // Distance between ranges doesn't fit in an offset
+        let line: MapLine = "18446744073709551000 0 10".parse().unwrap();
+        assert!(line.try_translate(&(0..5)).is_err());
+        assert!(adjust_range(5..10, -6).is_err());
+    }
+
+    #[test]
+    fn coalesce_fragmented_ranges() {
+        let mut ranges = vec![20..25, 7..10, 5..7, 30..30, 8..12, 24..26, 40..41];
+        let min = ranges.iter().map(|range| range.start).min();
+
+        coalesce(&mut ranges);
+        assert_eq!(ranges, &[5..12, 20..26, 40..41]);
+        assert_eq!(ranges.iter().map(|range| range.start).min(), min);
+    }
+
+    #[test]
+    fn broken_map_chain() {
+        let input = EXAMPLE.replace("water-to-light", "water-to-lamp");
+        assert!(parse(&input).unwrap_err().to_string().contains("no map from category 'lamp'"));
+    }
+
+    #[test]
+    fn cyclic_map_chain() {
+        let input = EXAMPLE.replace("humidity-to-location", "humidity-to-soil");
+        assert!(parse(&input).unwrap_err().to_string().contains("cyclic"));
+    }
+
     #[test]
     fn parse_ranges() {
         let line: MapLine = "50 98 2".parse().unwrap();
@@ -225,15 +511,37 @@ mod tests {
 
     #[test]
     fn parse_single_seeds() {
-        let seeds: Seeds = Seeds::from_singles_str("seeds: 79 14 55 13");
+        let seeds: Seeds = Seeds::from_singles_str("seeds: 79 14 55 13").unwrap();
         assert_eq!(seeds.0, &[79..80, 14..15, 55..56, 13..14]);
     }
 
     #[test]
     fn parse_seed_range() {
-        let seeds: Seeds = Seeds::from_ranges_str("seeds: 79 14 55 13");
+        let seeds: Seeds = Seeds::from_ranges_str("seeds: 79 14 55 13").unwrap();
         assert_eq!(seeds.0, &[79..79+14, 55..55+13]);
     }
+
+    #[test]
+    fn malformed_seeds() {
+        assert!(Seeds::from_singles_str("79 14 55 13").unwrap_err().to_string().contains("prefix"));
+        assert!(Seeds::from_ranges_str("sedes: 79 14").unwrap_err().to_string().contains("prefix"));
+        assert!(Seeds::from_singles_str("seeds: 79 1x4").unwrap_err().to_string().contains("'1x4'"));
+        assert!(Seeds::from_ranges_str("seeds: 79 -14").unwrap_err().to_string().contains("'-14'"));
+    }
+
+    #[test]
+    fn dangling_seed_range() {
+        let err = Seeds::from_ranges_str("seeds: 79 14 55").unwrap_err();
+        assert_eq!(err.to_string(), "seed range starting at 55 has no length");
+        assert!(Seeds::from_singles_str("seeds: 79 14 55").is_ok());
+    }
+
+    #[test]
+    fn zero_length_seed_range() {
+        let err = Seeds::from_ranges_str("seeds: 10 0").unwrap_err();
+        assert_eq!(err.to_string(), "seed range starting at 10 has zero length");
+        assert!(Seeds::from_singles_str("seeds: 10 0").is_ok());
+    }
 }
 
 
@@ -243,50 +551,84 @@ impl FromStr for MapLine {
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let mut split = s.split_ascii_whitespace();
-        let dst_start: usize = split.next()
+        let dst_start: u64 = split.next()
             .ok_or_else(|| anyhow!("missing dst start"))?
             .parse()?;
-        let src_start: usize = split.next()
+        let src_start: u64 = split.next()
             .ok_or_else(|| anyhow!("missing src start"))?
             .parse()?;
-        let range_len: usize = split.next()
+        let range_len: u64 = split.next()
             .ok_or_else(|| anyhow!("missing range length"))?
             .parse()?;
 
+        let end = |start: u64| start.checked_add(range_len)
+            .ok_or_else(|| anyhow!("range starting at {start} with length {range_len} overflows"));
+
         Ok(Self {
-            dst: dst_start..(dst_start + range_len),
-            src: src_start..(src_start + range_len),
+            dst: dst_start..end(dst_start)?,
+            src: src_start..end(src_start)?,
         })
     }
 }
 
+/// Formats line as `dst src len`, same as in puzzle input.
+impl std::fmt::Display for MapLine {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {} {}", self.dst.start, self.src.start, self.src.end - self.src.start)
+    }
+}
+
+/// Formats map as a `<from>-to-<to> map:` header followed by its lines,
+/// same as in puzzle input.
+impl std::fmt::Display for Map {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{}-to-{} map:", self.from, self.to)?;
+        for line in &self.lines {
+            writeln!(f, "{line}")?;
+        }
+        Ok(())
+    }
+}
 
 #[derive(Debug)]
-struct Seeds(Vec<Range<usize>>);
+struct Seeds(Vec<Range<u64>>);
 impl Seeds {
-    fn from_ranges_str(s: &str) -> Self {
-        let s = s.strip_prefix("seeds: ").unwrap();
-        let mut parts = s.split_ascii_whitespace();
-
-        let mut out = Vec::new();
-        while let (Some(Ok(start)), Some(Ok(length))) = (
-            parts.next().map(|p| p.parse::<usize>()),
-            parts.next().map(|p| p.parse::<usize>()),
-        )
-        {
-            out.push(start..start+length)
+    /// Parse seeds given as `start length` pairs.
+    fn from_ranges_str(s: &str) -> anyhow::Result<Self> {
+        let numbers = Self::parse_numbers(s)?;
+        if numbers.len() % 2 != 0 {
+            return Err(anyhow!("seed range starting at {} has no length", numbers[numbers.len() - 1]));
         }
 
-        Self(out)
+        numbers.chunks_exact(2)
+            .map(|pair| match (pair[0], pair[1]) {
+                (start, 0) => Err(anyhow!("seed range starting at {start} has zero length")),
+                (start, len) => start.checked_add(len)
+                    .map(|end| start..end)
+                    .ok_or_else(|| anyhow!("seed range starting at {start} with length {len} overflows")),
+            })
+            .collect::<anyhow::Result<_>>()
+            .map(Self)
+    }
+
+    /// Parse seeds given as individual numbers.
+    fn from_singles_str(s: &str) -> anyhow::Result<Self> {
+        let numbers = Self::parse_numbers(s)?;
+        numbers.into_iter()
+            .map(|start| start.checked_add(1)
+                .map(|end| start..end)
+                .ok_or_else(|| anyhow!("seed {start} is too large")))
+            .collect::<anyhow::Result<_>>()
+            .map(Self)
     }
 
-    fn from_singles_str(s: &str) -> Self {
-        let s = s.strip_prefix("seeds: ").unwrap();
-        let inner = s.split_ascii_whitespace().map(|num| {
-            let start = num.parse::<usize>().unwrap();
-            start..start+1
-        });
+    /// Parse numbers of a `seeds: ` line.
+    fn parse_numbers(s: &str) -> anyhow::Result<Vec<u64>> {
+        let s = s.strip_prefix("seeds: ")
+            .ok_or_else(|| anyhow!("seed line is missing 'seeds: ' prefix"))?;
 
-        Self(inner.collect())
+        s.split_ascii_whitespace()
+            .map(|num| num.parse::<u64>().map_err(|err| anyhow!("invalid seed '{num}': {err}")))
+            .collect()
     }
 }
@@ -1,4 +1,5 @@
-use advent::read_input;
+use advent::{read_input, run_repeated, part_filter, Answers, timed};
+use anyhow::anyhow;
 
 #[derive(Debug)]
 struct Race {
@@ -7,67 +8,195 @@ struct Race {
 }
 
 impl Race {
-    /// Calculate how many possible ways there are to win this race
-    fn number_of_wins(&self) -> usize {
-        let mut result = 0;
+    /// Whether holding the button for `hold` ms beats the record.
+    fn wins(&self, hold: usize) -> bool {
+        hold * (self.time - hold) > self.record
+    }
 
-        for holding in 0..self.time {
-            let speed = holding;
-            let remaining = self.time - holding;
+    /// Inclusive range of hold times which beat the record, if any.
+    ///
+    /// Distance is `hold * (time - hold)`, so winning holds lie between
+    /// the roots of `hold^2 - time*hold + record = 0`.
+    fn winning_range(&self) -> Option<(usize, usize)> {
+        let time = self.time as f64;
+        let discriminant = time * time - 4.0 * self.record as f64;
+        if discriminant < 0.0 {
+            return None;
+        }
 
-            let distance_covered = speed * remaining;
+        let mut min = ((time - discriminant.sqrt()) / 2.0).floor().max(0.0) as usize;
+        // Floating point roots may be slightly off on long races
+        while min > 0 && self.wins(min - 1) {
+            min -= 1;
+        }
+        while min <= self.time / 2 && !self.wins(min) {
+            min += 1;
+        }
 
-            if distance_covered > self.record {
-                result += 1;
-            }
+        // Range is symmetric, if the middle doesn't win nothing does
+        if min > self.time / 2 {
+            return None;
         }
 
-        result
+        Some((min, self.time - min))
     }
+
+    /// Calculate how many possible ways there are to win this race
+    fn number_of_wins(&self) -> usize {
+        self.winning_range()
+            .map(|(min, max)| max - min + 1)
+            .unwrap_or(0)
+    }
+}
+
+/// Race sheet with times and record distances kept as digit strings,
+/// so they can be read either as separate races or as one long race.
+#[derive(Debug)]
+struct Sheet<'a> {
+    times: Vec<&'a str>,
+    records: Vec<&'a str>,
 }
 
-enum ParseMode {
-    Multiple, // Silver part
-    Single,   // Gold part
+impl Sheet<'_> {
+    /// Each column of the sheet is a separate race (silver).
+    fn races(&self) -> anyhow::Result<Vec<Race>> {
+        self.times.iter().zip(&self.records)
+            .map(|(time, record)| Ok(Race { time: time.parse()?, record: record.parse()? }))
+            .collect()
+    }
+
+    /// Digits of each line form a single race (gold).
+    fn single_race(&self) -> anyhow::Result<Race> {
+        Ok(Race {
+            time: self.times.concat().parse()?,
+            record: self.records.concat().parse()?,
+        })
+    }
 }
 
-fn parse(input: &str, mode: ParseMode) -> Vec<Race> {
-    let mut lines = input.lines();
-    let times = lines.next().expect("missing 'times' line");
-    let records = lines.next().expect("missing 'record distance' line");
+/// Parse a race sheet of a times line and a record distances line.
+///
+/// Lines may be labeled, e.g. `Time:`, and blank lines around them are skipped.
+fn parse(input: &str) -> anyhow::Result<Sheet<'_>> {
+    let mut lines = input.lines().filter(|line| !line.trim().is_empty());
+    let times = lines.next().ok_or_else(|| anyhow!("missing 'times' line"))?;
+    let records = lines.next().ok_or_else(|| anyhow!("missing 'record distance' line"))?;
 
-    let (_, times) = times.split_once(':').unwrap();
-    let (_, records) = records.split_once(':').unwrap();
+    if let Some(extra) = lines.next() {
+        return Err(anyhow!("unexpected line '{extra}' after record distances"));
+    }
 
+    // Label is everything before ':', if there's one
+    fn numbers(line: &str) -> Vec<&str> {
+        let (_, numbers) = line.split_once(':').unwrap_or(("", line));
+        numbers.split_ascii_whitespace().collect()
+    }
 
-    let (times, records) = match mode {
-        ParseMode::Multiple => (times.to_string(), records.to_string()),
-        ParseMode::Single => (
-            times.chars().filter(|c| c.is_ascii_digit()).collect(),
-            records.chars().filter(|c| c.is_ascii_digit()).collect()
-        ),
+    let sheet = Sheet {
+        times: numbers(times),
+        records: numbers(records),
     };
 
-    let mut out: Vec<Race> = Vec::new();
-    for (time, record) in times.split_ascii_whitespace().zip(records.split_ascii_whitespace()) {
-        out.push(Race {
-            time: time.parse().unwrap(),
-            record: record.parse().unwrap(),
-        });
+    if sheet.times.len() != sheet.records.len() {
+        return Err(anyhow!("{} times but {} record distances", sheet.times.len(), sheet.records.len()));
     }
 
-    out
+    Ok(sheet)
+}
+
+/// Solve the puzzle for given input.
+pub fn run(input: &str) -> anyhow::Result<Answers> {
+    let sheet = timed("parse", || parse(input))?;
+    let part = part_filter();
+
+    Ok(Answers::new(
+        part.silver(|| -> anyhow::Result<usize> {
+            let races = sheet.races()?;
+            Ok(timed("silver", || races.iter().map(Race::number_of_wins).product()))
+        }).transpose()?,
+        part.gold(|| -> anyhow::Result<usize> {
+            let race = sheet.single_race()?;
+            Ok(timed("gold", || race.number_of_wins()))
+        }).transpose()?,
+    ))
 }
 
 fn main() -> anyhow::Result<()> {
     let input = read_input()?;
-    let silver_races = parse(&input, ParseMode::Multiple);
-    let gold_race = parse(&input, ParseMode::Single);
+    run_repeated(&input, run)?.emit();
 
-    let silver_sum: usize = silver_races.iter().map(|race| race.number_of_wins()).product();
+    Ok(())
+}
 
-    println!("Silver: {}", silver_sum);
-    println!("  Gold: {}", gold_race.first().unwrap().number_of_wins());
 
-    Ok(())
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+Time:      7  15   30
+Distance:  9  40  200
+";
+
+    #[test]
+    fn winning_ranges() {
+        let sheet = parse(EXAMPLE).unwrap();
+
+        let races = sheet.races().unwrap();
+        let ranges: Vec<_> = races.iter().map(Race::winning_range).collect();
+        assert_eq!(ranges, [Some((2, 5)), Some((4, 11)), Some((11, 19))]);
+        assert_eq!(races.iter().map(Race::number_of_wins).product::<usize>(), 288);
+
+        let race = sheet.single_race().unwrap();
+        assert_eq!(race.winning_range(), Some((14, 71516)));
+        assert_eq!(race.number_of_wins(), 71503);
+    }
+
+    #[test]
+    fn both_parts_from_one_parse() {
+        let sheet = parse(EXAMPLE).unwrap();
+        assert_eq!(sheet.times, ["7", "15", "30"]);
+        assert_eq!(sheet.records, ["9", "40", "200"]);
+
+        let races = sheet.races().unwrap();
+        assert_eq!(races.iter().map(|race| (race.time, race.record)).collect::<Vec<_>>(), [(7, 9), (15, 40), (30, 200)]);
+
+        let race = sheet.single_race().unwrap();
+        assert_eq!((race.time, race.record), (71530, 940200));
+
+        assert!(parse("Time: 7 15\nDistance: 9\n").is_err());
+    }
+
+    #[test]
+    fn no_winning_holds() {
+        // Best hold only ties the record
+        let race = Race { time: 4, record: 4 };
+        assert_eq!(race.winning_range(), None);
+        assert_eq!(race.number_of_wins(), 0);
+
+        assert_eq!(Race { time: 3, record: 100 }.winning_range(), None);
+    }
+
+    #[test]
+    fn unlabeled_sheet() {
+        let sheet = parse("\n  7  15   30  \n  9  40  200\n\n").unwrap();
+        assert_eq!(sheet.times, ["7", "15", "30"]);
+        assert_eq!(sheet.records, ["9", "40", "200"]);
+        assert_eq!(sheet.races().unwrap().iter().map(Race::number_of_wins).product::<usize>(), 288);
+
+        // Labels may be mixed too
+        assert_eq!(parse("7 15\nDistance: 9 40\n").unwrap().records, ["9", "40"]);
+    }
+
+    #[test]
+    fn mismatched_counts() {
+        let err = parse("Time: 7 15 30\nDistance: 9 40\n").unwrap_err();
+        assert_eq!(err.to_string(), "3 times but 2 record distances");
+
+        let err = parse("Time: 7\n").unwrap_err();
+        assert_eq!(err.to_string(), "missing 'record distance' line");
+
+        let err = parse("Time: 7\nDistance: 9\nTime: 8\n").unwrap_err();
+        assert_eq!(err.to_string(), "unexpected line 'Time: 8' after record distances");
+    }
 }
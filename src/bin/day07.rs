@@ -1,9 +1,9 @@
 use std::cmp::Ordering;
 
-use advent::read_input;
+use advent::{read_input, run_repeated, part_filter, Answers, timed};
 use anyhow::anyhow;
 
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 enum Win {
     FiveOfAKind = 0,
     FourOfAKind,
@@ -14,10 +14,14 @@ enum Win {
     HighCard,
 }
 
-#[derive(Debug, Eq, Ord)]
+#[derive(Debug, Eq)]
 struct Hand {
     cards: Vec<u8>,
     bid: usize,
+    /// Win type of `cards`, computed once on construction
+    win: Win,
+    /// Whether `J` is a jack (silver) rather than a joker (gold)
+    silver_joker: bool,
 }
 
 impl PartialEq for Hand {
@@ -29,25 +33,28 @@ impl PartialEq for Hand {
 
 impl PartialOrd for Hand {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        match self.wintype().partial_cmp(&other.wintype()) {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Hand {
+    /// Stronger hands come first, hands with the same cards are equal.
+    fn cmp(&self, other: &Self) -> Ordering {
+        match self.win.cmp(&other.win) {
             // Cards have winning type,
             // need to check individual cards
-            Some(Ordering::Equal) => if self.is_stronger(&other) { 
-                Some(Ordering::Less)
-            } else {
-                Some(Ordering::Greater)
-            },
+            Ordering::Equal => other.cards.cmp(&self.cards),
             // Hand's type differs, delegate win resolution to 
-            Some(order) => Some(order),
-            None => unreachable!(),
+            order => order,
         }
     }
 }
 
 impl Hand {
-    fn wintype(&self) -> Win {
+    /// Classify cards by their win type, jokers have value `1`.
+    fn wintype(cards: &[u8]) -> Win {
         let mut counts = [0_u8; 14];
-        for &c in &self.cards {
+        for &c in cards {
             counts[c as usize - 1] += 1;
         }
 
@@ -61,7 +68,7 @@ impl Hand {
         let max_same = *counts.iter().max().unwrap();
         let pairs = counts.iter().filter(|&&count| count == 2).count();
 
-        return match (max_same, jokers) {
+        match (max_same, jokers) {
             // Check if we have N-of-a-kind using cards+jokers
             fives @ (0..=5, _) if fives.0 + jokers == 5 => Win::FiveOfAKind,
             fours @ (0..=4, _) if fours.0 + jokers == 4 => Win::FourOfAKind,
@@ -82,50 +89,52 @@ impl Hand {
             _      => unreachable!(),
         }
     }
-
-    fn is_stronger(&self, other: &Self) -> bool {
-        debug_assert_eq!(self.wintype(), other.wintype());
-
-        for (&first, &second) in self.cards.iter().zip(other.cards.iter()) {
-            if first == second {
-                continue;
-            }
-            return first > second
-        }
-
-        unreachable!();
-    }
 }
 
+/// Total winnings of `hands`.
+///
+/// Identical hands are ranked by their bids, the larger bid ranks higher.
+/// Otherwise `sort_unstable` could order them either way and the winnings
+/// would depend on the sort. Hands with different cards are never tied,
+/// so their ranks don't depend on bids.
 fn solve(hands: &mut [Hand]) -> usize {
     let mut sum: usize = 0;
 
-    hands.sort_unstable();
+    hands.sort_unstable_by(|a, b| a.cmp(b).then(b.bid.cmp(&a.bid)));
     for (hand, rank) in hands.iter().rev().zip(1_usize..) {
         sum += rank * hand.bid;
     }
     sum
 }
 
-fn main() -> anyhow::Result<()> {
-    let input = read_input()?;
-
-    let mut silver_hands: Vec<Hand> = input.trim().lines()
-        .map(|line| Hand::from_str::<true>(line).unwrap())
-        .collect();
-    let silver_sum = solve(&mut silver_hands);
+/// Solve the puzzle for given input.
+pub fn run(input: &str) -> anyhow::Result<Answers> {
+    let part = part_filter();
 
-    let mut gold_hands: Vec<Hand> = input.trim().lines()
-        .map(|line| Hand::from_str::<false>(line).unwrap())
-        .collect();
-    let gold_sum = solve(&mut gold_hands);
+    Ok(Answers::new(
+        part.silver(|| -> anyhow::Result<usize> {
+            let mut silver_hands = timed("parse silver", || parse::<true>(input))?;
+            Ok(timed("silver", || solve(&mut silver_hands)))
+        }).transpose()?,
+        part.gold(|| -> anyhow::Result<usize> {
+            let mut gold_hands = timed("parse gold", || parse::<false>(input))?;
+            Ok(timed("gold", || solve(&mut gold_hands)))
+        }).transpose()?,
+    ))
+}
 
-    println!("Silver: {}", silver_sum);
-    println!("  Gold: {}", gold_sum);
+fn main() -> anyhow::Result<()> {
+    let input = read_input()?;
+    run_repeated(&input, run)?.emit();
 
     Ok(())
 }
 
+/// Parse hands, `S` selects silver jacks over gold jokers.
+fn parse<const S: bool>(input: &str) -> anyhow::Result<Vec<Hand>> {
+    input.trim().lines().map(Hand::from_str::<S>).collect()
+}
+
 fn card_value(c: char, silver_joker: bool) -> u8 {
     match c as u8 {
         // ASCII digits
@@ -139,14 +148,156 @@ fn card_value(c: char, silver_joker: bool) -> u8 {
     }
 }
 
+/// Inverse of [`card_value`].
+fn card_char(value: u8, silver_joker: bool) -> char {
+    match value {
+        1 if !silver_joker => 'J',
+        11 if silver_joker => 'J',
+        num @ 2..=9 => (b'0' + num) as char,
+        10 => 'T',
+        12 => 'Q',
+        13 => 'K',
+        14 => 'A',
+        _ => panic!("invalid card value {value}")
+    }
+}
+
 impl Hand {
     fn from_str<const S: bool>(s: &str) -> anyhow::Result<Self> {
         let (hand, bid) = s.split_once(' ').ok_or_else(|| anyhow!("invalid line format"))?;
+        let n_cards = hand.chars().count();
+        if n_cards != 5 {
+            return Err(anyhow!("hand '{hand}' has {n_cards} cards, expected 5"));
+        }
+
+        let cards: Vec<u8> = hand.chars().map(|c| card_value(c, S)).collect();
         let inner = Self {
-            cards: hand.chars().take(5).map(|c| card_value(c, S)).collect(),
-            bid: bid.parse()?
+            win: Self::wintype(&cards),
+            cards,
+            bid: bid.parse()?,
+            silver_joker: S,
         };
 
         Ok(inner)
     }
 }
+
+/// Formats hand as `cards bid`, same as in puzzle input.
+impl std::fmt::Display for Hand {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for &card in &self.cards {
+            write!(f, "{}", card_char(card, self.silver_joker))?;
+        }
+        write!(f, " {}", self.bid)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Win type of given cards, `S` selects silver jacks over gold jokers.
+    fn wintype<const S: bool>(cards: &str) -> Win {
+        Hand::from_str::<S>(&format!("{cards} 0")).unwrap().win
+    }
+
+    #[test]
+    fn silver_wintypes() {
+        let cases = [
+            ("AAAAA", Win::FiveOfAKind),
+            ("JJJJJ", Win::FiveOfAKind),
+            ("AAAAK", Win::FourOfAKind),
+            ("AAAKK", Win::FullHouse),
+            ("AAAKQ", Win::ThreeOfKind),
+            ("AAKKQ", Win::TwoPair),
+            ("AAKQJ", Win::OnePair),
+            ("AKQJT", Win::HighCard),
+        ];
+
+        for (cards, expected) in cases {
+            assert_eq!(wintype::<true>(cards), expected, "{cards}");
+        }
+    }
+
+    #[test]
+    fn gold_wintypes() {
+        let cases = [
+            ("AAAAA", Win::FiveOfAKind),
+            ("JJJJJ", Win::FiveOfAKind),
+            ("AJJJJ", Win::FiveOfAKind),
+            ("AAJJJ", Win::FiveOfAKind), // (2, 3)
+            ("AAAAJ", Win::FiveOfAKind),
+            ("AAAAK", Win::FourOfAKind),
+            ("AKJJJ", Win::FourOfAKind),
+            ("AAAKJ", Win::FourOfAKind),
+            ("AAKKJ", Win::FullHouse), // (2, 1) with two pairs
+            ("AAAKK", Win::FullHouse),
+            ("AAKQJ", Win::ThreeOfKind),
+            ("AKQJJ", Win::ThreeOfKind),
+            ("AAAKQ", Win::ThreeOfKind),
+            ("AAKKQ", Win::TwoPair),
+            ("AKQTJ", Win::OnePair),
+            ("AAKQT", Win::OnePair),
+            ("AKQT9", Win::HighCard),
+        ];
+
+        for (cards, expected) in cases {
+            assert_eq!(wintype::<false>(cards), expected, "{cards}");
+        }
+    }
+
+    #[test]
+    fn hand_needs_five_cards() {
+        let short = Hand::from_str::<true>("AKQJ 10").unwrap_err();
+        assert_eq!(short.to_string(), "hand 'AKQJ' has 4 cards, expected 5");
+
+        let long = Hand::from_str::<false>("AKQJT9 10").unwrap_err();
+        assert_eq!(long.to_string(), "hand 'AKQJT9' has 6 cards, expected 5");
+    }
+
+    #[test]
+    fn display_roundtrip() {
+        let input = "32T3K 765\nT55J5 684\nKK677 28\nKTJJT 220\nQQQJA 483\nA2345 1\n";
+
+        for line in input.lines() {
+            assert_eq!(Hand::from_str::<true>(line).unwrap().to_string(), line);
+            assert_eq!(Hand::from_str::<false>(line).unwrap().to_string(), line);
+        }
+
+        let mut hands = parse::<false>(input).unwrap();
+        solve(&mut hands);
+        assert_eq!(hands[0].to_string(), "KTJJT 220");
+    }
+
+    #[test]
+    fn duplicate_hands() {
+        let input = "KK677 10\n32T3K 5\nKK677 20\n";
+        let hands = parse::<true>(input).unwrap();
+        assert_eq!(hands[0].cmp(&hands[2]), Ordering::Equal);
+
+        // 32T3K ranks 1, KK677 bidding 10 ranks 2 and KK677 bidding 20 ranks 3
+        let expected = 5 + 2 * 10 + 3 * 20;
+        assert_eq!(solve(&mut parse::<true>(input).unwrap()), expected);
+        assert_eq!(solve(&mut parse::<false>(input).unwrap()), expected);
+
+        let reordered = "KK677 20\nKK677 10\n32T3K 5\n";
+        assert_eq!(solve(&mut parse::<true>(reordered).unwrap()), expected);
+    }
+
+    #[test]
+    fn ordering_uses_stored_wintype() {
+        let mut high = Hand::from_str::<true>("AKQT9 1").unwrap();
+        let pair = Hand::from_str::<true>("22345 2").unwrap();
+        assert_eq!(high.cmp(&pair), Ordering::Greater);
+
+        // Ordering must not reclassify cards, only the stored win type counts
+        high.win = Win::FiveOfAKind;
+        assert_eq!(high.cmp(&pair), Ordering::Less);
+
+        let mut hands = [pair, high];
+        // 22345 ranks 1 bidding 2, AKQT9 ranks 2 bidding 1
+        assert_eq!(solve(&mut hands), 4);
+        assert_eq!(hands[0].to_string(), "AKQT9 1");
+    }
+}
@@ -1,6 +1,7 @@
-use std::{str::FromStr, convert::Infallible, collections::HashMap};
+use std::{str::FromStr, collections::HashMap};
 
-use advent::read_input;
+use advent::{read_input, run_repeated, part_filter, Answers, dbgln, timed};
+use anyhow::{anyhow, Context};
 
 // https://github.com/TheAlgorithms/Rust/blob/master/src/math/lcm_of_n_numbers.rs
 pub fn lcm(nums: &[usize]) -> usize {
@@ -29,124 +30,329 @@ struct Instructions {
 }
 
 impl FromStr for Instructions {
-    type Err = Infallible;
+    type Err = anyhow::Error;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         Ok(Self {
             dirs: s.chars().map(|c| match c {
-                'L' => Direction::Left,
-                'R' => Direction::Right,
-                _   => panic!("invalid direction"),
-            }).collect()
+                'L' => Ok(Direction::Left),
+                'R' => Ok(Direction::Right),
+                _   => Err(anyhow!("invalid direction '{c}'")),
+            }).collect::<anyhow::Result<_>>()?
         })
     }
 }
 
-fn parse<'a>(lines: impl Iterator<Item = &'a str>) -> HashMap<String, (String, String)> {
+/// Nodes by name, along with their `(left, right)` neighbours
+type Nodes = HashMap<String, (String, String)>;
+
+/// Parse instructions and the map of nodes.
+fn parse(input: &str) -> anyhow::Result<(Instructions, Nodes)> {
+    let mut lines = input.trim().lines().zip(1..);
+    let (first, _) = lines.next().ok_or_else(|| anyhow!("missing instructions"))?;
+    let instructions = first.parse().context("line 1")?;
+    let _ = lines.next();
+
     let mut out = HashMap::new();
 
-    for line in lines {
-        let (origin, dest) = line.split_once(" = ").expect("invalid line");
+    for (line, i) in lines {
         // origin -> AAA
         // dest   -> (BBB, CCC)
-        let (left, right) = dest
-            .strip_prefix('(').unwrap()
-            .strip_suffix(')').unwrap()
-            .split_once(", ").unwrap();
+        let parse_line = || -> anyhow::Result<(&str, &str, &str)> {
+            let (origin, dest) = line.split_once(" = ")
+                .ok_or_else(|| anyhow!("missing ' = ' separator"))?;
+            let (left, right) = dest
+                .strip_prefix('(')
+                .and_then(|dest| dest.strip_suffix(')'))
+                .ok_or_else(|| anyhow!("destinations are not enclosed in parentheses"))?
+                .split_once(", ")
+                .ok_or_else(|| anyhow!("missing ', ' between destinations"))?;
+
+            Ok((origin, left, right))
+        };
+        let (origin, left, right) = parse_line().with_context(|| format!("line {i}: {line}"))?;
 
         out.insert(origin.to_string(), (left.to_string(), right.to_string()));
     }
 
-    out
+    Ok((instructions, out))
+}
+
+/// Nodes interned to indices, so traversal doesn't need to hash names.
+#[derive(Debug)]
+struct Graph {
+    /// Node names by index
+    names: Vec<String>,
+    /// Node indices by name
+    indices: HashMap<String, usize>,
+    /// `(left, right)` neighbours by index
+    edges: Vec<(usize, usize)>,
+}
+
+impl Graph {
+    /// Intern nodes of `map`, indices follow the order of names.
+    ///
+    /// Returns an error if some node leads to a node which isn't in the map.
+    fn from_nodes(map: &Nodes) -> anyhow::Result<Self> {
+        let mut names: Vec<String> = map.keys().cloned().collect();
+        names.sort_unstable();
+
+        let indices: HashMap<String, usize> = names.iter()
+            .enumerate()
+            .map(|(i, name)| (name.clone(), i))
+            .collect();
+
+        let index = |name: &String| indices.get(name)
+            .copied()
+            .ok_or_else(|| anyhow!("no such node in map: {name}"));
+        let edges = names.iter()
+            .map(|name| {
+                let (left, right) = &map[name];
+                Ok((index(left)?, index(right)?))
+            })
+            .collect::<anyhow::Result<_>>()?;
+
+        Ok(Self { names, indices, edges })
+    }
+
+    /// Index of the node reached from `node` by following `direction`.
+    fn step(&self, node: usize, direction: Direction) -> usize {
+        match direction {
+            Direction::Left => self.edges[node].0,
+            Direction::Right => self.edges[node].1,
+        }
+    }
+}
+
+/// Default step limit of [`silver`], enough to visit every node
+/// at every instruction.
+fn default_max_steps(instructions: &Instructions, graph: &Graph) -> usize {
+    graph.edges.len() * instructions.dirs.len()
+}
+
+/// Default step limit of [`gold`], which has to reach an end node twice,
+/// the second time up to a full cycle after the first.
+fn default_gold_max_steps(instructions: &Instructions, graph: &Graph) -> usize {
+    2 * default_max_steps(instructions, graph)
 }
 
+/// Count steps from `AAA` to `ZZZ`, giving up after `max_steps`.
+#[allow(dead_code)]
 fn silver(
     instructions: &Instructions,
-    map: &HashMap<String, (String, String)>
-) -> usize {
-    let mut visitor = &String::from("AAA");
+    graph: &Graph,
+    max_steps: usize,
+) -> anyhow::Result<usize> {
+    let node = |name: &str| graph.indices.get(name)
+        .copied()
+        .ok_or_else(|| anyhow!("no such node in map: {name}"));
+    let mut visitor = node("AAA")?;
+    let end = node("ZZZ")?;
     let mut steps = 0;
 
-    for instruction in instructions.dirs.iter().cycle() {
-        let Some(directions) = map.get(visitor) else {
-            panic!("no such node in map: {}", visitor);
-        };
-
-        match instruction {
-            Direction::Left => visitor = &directions.0,
-            Direction::Right => visitor = &directions.1,
+    for &instruction in instructions.dirs.iter().cycle() {
+        if steps == max_steps {
+            return Err(anyhow!("ZZZ not reached within {max_steps} steps"));
         }
 
+        visitor = graph.step(visitor, instruction);
+
         steps += 1;
-        if visitor == "ZZZ" {
+        if visitor == end {
             // println!("reached ZZZ!");
             break;
         }
     }
 
-    steps
+    Ok(steps)
 }
 
+/// Count steps until all `..A` nodes are at `..Z` nodes at once,
+/// giving up if cycles haven't been found after `max_steps`.
+///
+/// Assumes each cursor reaches its first `..Z` node after exactly one cycle,
+/// as in the puzzle input, and returns an error otherwise.
 fn gold(
     instructions: &Instructions,
-    map: &HashMap<String, (String, String)>
-) -> usize {
+    graph: &Graph,
+    max_steps: usize,
+) -> anyhow::Result<usize> {
     // First, find all starting positions
-    let mut cursors: Vec<&String> = map.keys().filter(|key| key.ends_with('A')).collect();
-    // List of current cycle lengths
-    let mut cycle_lengths = vec![0_usize; cursors.len()];
-    // List of stable cycle lengths, i.e. the true length of the cycle
-    // There is always one node in a cycle
-    let mut stable = vec![1_usize; cursors.len()];
+    let mut cursors: Vec<usize> = (0..graph.names.len())
+        .filter(|&node| graph.names[node].ends_with('A'))
+        .collect();
+    if cursors.is_empty() {
+        return Err(anyhow!("no starting nodes in map"));
+    }
+    // Whether each node is an end node, so names aren't checked on every step
+    let is_end: Vec<bool> = graph.names.iter().map(|name| name.ends_with('Z')).collect();
+    // Steps until each cursor first reached an end node
+    let mut first_ends: Vec<Option<usize>> = vec![None; cursors.len()];
+    // Steps between the first and second end node of each cursor
+    let mut cycles: Vec<Option<usize>> = vec![None; cursors.len()];
 
-    println!("starting with {} cursors", cursors.len());
+    dbgln!("starting with {} cursors", cursors.len());
 
     // Run instructions until we have gathered all cycle lengths
-    for instruction in instructions.dirs.iter().cycle() {
-        for (i, cursor) in cursors.iter_mut().enumerate() {
-            let Some(directions) = map.get(&**cursor) else {
-                panic!("no such node in map: {}", cursor);
-            };
+    for (&instruction, step) in instructions.dirs.iter().cycle().zip(1..) {
+        if step > max_steps {
+            return Err(anyhow!("cycles not found within {max_steps} steps"));
+        }
 
+        for (i, cursor) in cursors.iter_mut().enumerate() {
             // Move cursor
-            match instruction {
-                Direction::Left => *cursor = &directions.0,
-                Direction::Right => *cursor = &directions.1,
-            }
+            *cursor = graph.step(*cursor, instruction);
 
-            if cursor.ends_with('Z') {
-                if cycle_lengths[i] > 0 {
-                    println!("cycle {i} is {}", cycle_lengths[i]);
-                    stable[i] = cycle_lengths[i];
+            if is_end[*cursor] {
+                match first_ends[i] {
+                    None => first_ends[i] = Some(step),
+                    Some(first) if cycles[i].is_none() => {
+                        dbgln!("cycle {i} is {}", step - first);
+                        cycles[i] = Some(step - first);
+                    }
+                    Some(_) => {}
                 }
-            } else {
-                cycle_lengths[i] += 1;
             }
         }
 
-        if stable.iter().all(|&n| n > 1) {
+        if cycles.iter().all(Option::is_some) {
             break;
         }
     }
 
-    println!("Stable cycle lengths: {:?}", stable);
+    let cycles: Vec<usize> = cycles.into_iter().flatten().collect();
+    for (i, (first, &cycle)) in first_ends.into_iter().flatten().zip(&cycles).enumerate() {
+        if first != cycle {
+            return Err(anyhow!("cursor {i} first reaches an end after {first} steps, but cycles every {cycle}"));
+        }
+    }
+
+    dbgln!("Stable cycle lengths: {:?}", cycles);
     // We have now gathered stable cycle counts.
     // Answer is least-common multiple of them all.
     // I.e. at what point all cycles align
-    lcm(&stable)
+    Ok(lcm(&cycles))
+}
+
+/// Solve the puzzle for given input.
+pub fn run(input: &str) -> anyhow::Result<Answers> {
+    let (instructions, map) = timed("parse", || parse(input))?;
+    // println!("{:#?}", map);
+    let graph = timed("intern", || Graph::from_nodes(&map))?;
+
+    let part = part_filter();
+
+    // println!("Silver: {}", silver(&instructions, &graph));
+    Ok(Answers::new(
+        None::<usize>,
+        part.gold(|| timed("gold", || gold(&instructions, &graph, default_gold_max_steps(&instructions, &graph)))).transpose()?,
+    ))
 }
 
 fn main() -> anyhow::Result<()> {
     let input = read_input()?;
-    let mut lines = input.trim().lines();
-    let instructions: Instructions = lines.next().unwrap().parse().unwrap();
-    let _ = lines.next();
+    run_repeated(&input, run)?.emit();
 
-    let map = parse(&mut lines);
-    // println!("{:#?}", map);
-    
+    Ok(())
+}
 
-    // println!("Silver: {}", silver(&instructions, &map));
-    println!("  Gold: {}", gold(&instructions, &map));
 
-    Ok(())
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_separator() {
+        let err = parse("LR\n\nAAA = (BBB, BBB)\nBBB (AAA, ZZZ)\n").unwrap_err();
+        assert_eq!(err.to_string(), "line 4: BBB (AAA, ZZZ)");
+        assert_eq!(err.root_cause().to_string(), "missing ' = ' separator");
+    }
+
+    /// Parse instructions and intern the nodes.
+    fn parse_graph(input: &str) -> (Instructions, Graph) {
+        let (instructions, map) = parse(input).unwrap();
+        (instructions, Graph::from_nodes(&map).unwrap())
+    }
+
+    #[test]
+    fn missing_node() {
+        let (_, map) = parse("L\n\nAAA = (BBB, BBB)\n").unwrap();
+        let err = Graph::from_nodes(&map).unwrap_err();
+        assert_eq!(err.to_string(), "no such node in map: BBB");
+
+        let (instructions, graph) = parse_graph("L\n\nBBB = (BBB, BBB)\n");
+        let err = silver(&instructions, &graph, 10).unwrap_err();
+        assert_eq!(err.to_string(), "no such node in map: AAA");
+    }
+
+    #[test]
+    fn step_cap() {
+        let (instructions, graph) = parse_graph("LLR\n\nAAA = (BBB, BBB)\nBBB = (AAA, ZZZ)\nZZZ = (ZZZ, ZZZ)\n");
+        assert_eq!(default_max_steps(&instructions, &graph), 9);
+
+        assert_eq!(silver(&instructions, &graph, 9).unwrap(), 6);
+        assert_eq!(silver(&instructions, &graph, 6).unwrap(), 6);
+        let err = silver(&instructions, &graph, 5).unwrap_err();
+        assert_eq!(err.to_string(), "ZZZ not reached within 5 steps");
+
+        assert!(gold(&instructions, &graph, 1).is_err());
+    }
+
+    #[test]
+    fn interned_example() {
+        let input = "\
+RL
+
+AAA = (BBB, CCC)
+BBB = (DDD, EEE)
+CCC = (ZZZ, GGG)
+DDD = (DDD, DDD)
+EEE = (EEE, EEE)
+GGG = (GGG, GGG)
+ZZZ = (ZZZ, ZZZ)
+";
+        let (instructions, graph) = parse_graph(input);
+        assert_eq!(graph.names, ["AAA", "BBB", "CCC", "DDD", "EEE", "GGG", "ZZZ"]);
+        assert_eq!(graph.indices["CCC"], 2);
+        assert_eq!(graph.edges[graph.indices["CCC"]], (6, 5));
+        assert_eq!(graph.step(0, Direction::Right), 2);
+
+        let max_steps = default_max_steps(&instructions, &graph);
+        assert_eq!(silver(&instructions, &graph, max_steps).unwrap(), 2);
+    }
+
+    #[test]
+    fn late_first_end() {
+        // The first end comes after visiting every node, the second a cycle later
+        let (instructions, graph) = parse_graph("\
+L
+
+AAA = (BBB, BBB)
+BBB = (CCC, CCC)
+CCC = (DDD, DDD)
+DDD = (ZZZ, ZZZ)
+ZZZ = (BBB, BBB)
+");
+        let err = gold(&instructions, &graph, default_max_steps(&instructions, &graph)).unwrap_err();
+        assert_eq!(err.to_string(), "cycles not found within 5 steps");
+        assert_eq!(gold(&instructions, &graph, default_gold_max_steps(&instructions, &graph)).unwrap(), 4);
+    }
+
+    #[test]
+    fn gold_example() {
+        let input = "\
+LR
+
+11A = (11B, XXX)
+11B = (XXX, 11Z)
+11Z = (11B, XXX)
+22A = (22B, XXX)
+22B = (22C, 22C)
+22C = (22Z, 22Z)
+22Z = (22B, 22B)
+XXX = (XXX, XXX)
+";
+        let (instructions, graph) = parse_graph(input);
+        let max_steps = default_gold_max_steps(&instructions, &graph);
+        assert_eq!(gold(&instructions, &graph, max_steps).unwrap(), 6);
+    }
 }
@@ -1,4 +1,4 @@
-use advent::read_input;
+use advent::{read_input, run_repeated, part_filter, Answers, dbgln, timed};
 
 fn all_elements_equal<I>(iter: I) -> Option<I::Item>
 where
@@ -6,9 +6,7 @@ where
     I::Item: PartialEq,
 {
     let mut iter = iter.into_iter();
-    let Some(head) = iter.next() else {
-        return None;
-    };
+    let head = iter.next()?;
 
     if iter.all(|elem| elem == head) {
         Some(head)
@@ -36,18 +34,27 @@ fn parse(line: &str) -> Vec<isize> {
     line.split_ascii_whitespace().map(|num| num.parse().unwrap()).collect()
 }
 
-fn main() -> anyhow::Result<()> {
-    let input = read_input()?;
+/// Solve the puzzle for given input.
+pub fn run(input: &str) -> anyhow::Result<Answers> {
     let lines = input.trim().lines();
+    let part = part_filter();
+
+    let silver_sum = part.gold(|| timed("solve", || {
+        let mut silver_sum = 0;
+        for line in lines {
+            let line = parse(line);
+            let extrapolated = extrapolate(&line);
+            silver_sum += extrapolated;
+            dbgln!("new: {extrapolated}\n");
+        }
+        silver_sum
+    }));
+    Ok(Answers::new(None::<isize>, silver_sum))
+}
 
-    let mut silver_sum = 0;
-    for line in lines {
-        let line = parse(line);
-        let extrapolated = extrapolate(&line);
-        silver_sum += extrapolated;
-        println!("new: {extrapolated}\n");
-    }
-    println!("Gold: {}", silver_sum);
+fn main() -> anyhow::Result<()> {
+    let input = read_input()?;
+    run_repeated(&input, run)?.emit();
 
     Ok(())
 }
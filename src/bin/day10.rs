@@ -1,6 +1,6 @@
 use std::{fmt::{Display, Debug, Write}, collections::HashSet};
 
-use advent::read_input;
+use advent::{read_input, run_repeated, part_filter, Answers, dbgln, debug_output, maybe_dump, shoelace_f64, timed, svg_output};
 use grid::Grid;
 
 #[derive(Clone, Copy, PartialEq)]
@@ -15,13 +15,13 @@ enum Tile {
     Start,      // S
 }
 
-fn parse(maze: &str) -> (Grid<Tile>, (usize, usize)) {
-    let cols = maze.lines().nth(0).expect("tried to parse empty maze").len();
+fn parse(maze: &str) -> anyhow::Result<(Grid<Tile>, (usize, usize))> {
+    let cols = maze.lines().next().expect("tried to parse empty maze").len();
     let mut everything: Vec<Tile> = Vec::new();
     let mut start = (0, 0);
 
     for (i, ch) in maze.chars().filter(|ch| !ch.is_ascii_whitespace()).enumerate() {
-        let tile = Tile::try_from(ch).unwrap();
+        let tile = Tile::try_from(ch)?;
         if tile == Tile::Start {
             start = (i / cols, i % cols);
         }
@@ -29,87 +29,83 @@ fn parse(maze: &str) -> (Grid<Tile>, (usize, usize)) {
     }
 
     let mut grid = Grid::from_vec(everything, cols);
-    grid[start] = resolve_unknown_tile(&grid, start);
+    grid[start] = resolve_unknown_tile(&grid, start)?;
 
-    (grid, start)
+    Ok((grid, start))
+}
+
+/// Row and column offsets towards each side of a tile.
+/// Orientation:
+///    N
+///    |
+/// W - - E
+///    |
+///    S
+const NORTH: (isize, isize) = (-1, 0);
+const SOUTH: (isize, isize) = (1, 0);
+const WEST: (isize, isize) = (0, -1);
+const EAST: (isize, isize) = (0, 1);
+
+/// All sides of a tile along with their names.
+const SIDES: [((isize, isize), &str); 4] = [(NORTH, "north"), (SOUTH, "south"), (WEST, "west"), (EAST, "east")];
+
+/// Position next to `pos` in direction of `offset`, if inside the maze.
+fn neighbour(maze: &Grid<Tile>, pos: (usize, usize), (row_offset, col_offset): (isize, isize)) -> Option<(usize, usize)> {
+    pos.0.checked_add_signed(row_offset)
+        .zip(pos.1.checked_add_signed(col_offset))
+        .filter(|&(row, col)| row < maze.rows() && col < maze.cols())
 }
 
 /// Observes neighbouring tiles to determine which tile given position should be.
-fn resolve_unknown_tile(maze: &Grid<Tile>, pos: (usize, usize)) -> Tile {
+///
+/// Returns an error if the neighbours don't connect to exactly two sides.
+fn resolve_unknown_tile(maze: &Grid<Tile>, pos: (usize, usize)) -> anyhow::Result<Tile> {
     debug_assert_eq!(maze[pos], Tile::Start);
 
-    let north_open = match maze.get(pos.0.wrapping_sub(1), pos.1) {
-        Some(Tile::Vertical | Tile::NorthEast | Tile::NorthWest) => true,
-        _ => false,
-    };
+    // Sides whose neighbour connects back towards `pos`
+    let open: Vec<((isize, isize), &str)> = SIDES.into_iter()
+        .filter(|&(side, _)| neighbour(maze, pos, side)
+            .is_some_and(|next| maze[next].connects((-side.0, -side.1))))
+        .collect();
 
-    let south_open = match maze.get(pos.0 + 1, pos.1) {
-        Some(Tile::Vertical | Tile::SouthEast | Tile::SouthWest) => true,
-        _ => false,
+    let tile = match open.as_slice() {
+        &[(first, _), (second, _)] => Tile::PIPES.into_iter()
+            .find(|tile| tile.connects(first) && tile.connects(second)),
+        _ => None,
     };
 
-    let west_open = match maze.get(pos.0, pos.1.wrapping_sub(1)) {
-        Some(Tile::Horizontal | Tile::NorthEast | Tile::SouthEast) => true,
-        _ => false,
-    };
+    tile.ok_or_else(|| {
+        let detected: Vec<&str> = open.iter().map(|&(_, name)| name).collect();
+        anyhow::anyhow!(
+            "cannot resolve start tile at {pos:?}, expected 2 openings but found {} ({})",
+            detected.len(),
+            if detected.is_empty() { "none".to_string() } else { detected.join(", ") },
+        )
+    })
+}
 
-    let east_open = match maze.get(pos.0, pos.1 + 1) {
-        Some(Tile::Horizontal | Tile::NorthWest | Tile::SouthWest) => true,
-        _ => false,
+/// Finds two possible coordinates one can move to from this point.
+///
+/// Returns an error if the pipe points outside the maze.
+fn get_possible_coords(maze: &Grid<Tile>, pos: (usize, usize)) -> anyhow::Result<[(usize, usize); 2]> {
+    let offsets = match maze[pos] {
+        Tile::Start => unreachable!("starting tile should have been resolved during grid creation"),
+        tile => tile.connections()
+            .ok_or_else(|| anyhow::anyhow!("loop leads onto ground at {pos:?}"))?,
     };
 
-    match (north_open, south_open, west_open, east_open) {
-        (true, true,    _,    _) => Tile::Vertical,
-        (   _,    _, true, true) => Tile::Horizontal,
-        (true,    _, true,    _) => Tile::NorthWest,
-        (true,    _,    _, true) => Tile::NorthEast,
-        (   _, true, true,    _) => Tile::SouthWest,
-        (   _, true,    _, true) => Tile::SouthEast,
+    let neighbour = |offset| neighbour(maze, pos, offset)
+        .ok_or_else(|| anyhow::anyhow!("pipe {:?} at {pos:?} points outside the maze", maze[pos]));
 
-        _ => unreachable!("pipe {} has more than 2 openings", maze[pos])
-    }
+    Ok([neighbour(offsets[0])?, neighbour(offsets[1])?])
 }
 
-/// Finds two possible coordinates one can move to from this point
-/// Orientation:
-///    N
-///    |
-/// W - - E
-///    |
-///    S
-fn get_possible_coords(maze: &Grid<Tile>, pos: (usize, usize)) -> [(usize, usize); 2] {
-    match maze[pos] {
-        Tile::Vertical => [
-            (pos.0 + 1, pos.1),
-            (pos.0 - 1, pos.1),
-        ],
-        Tile::Horizontal => [
-            (pos.0, pos.1 + 1),
-            (pos.0, pos.1 - 1),
-        ],
-        Tile::NorthEast => [
-            (pos.0 - 1, pos.1),
-            (pos.0, pos.1 + 1),
-        ],
-        Tile::NorthWest => [
-            (pos.0 - 1, pos.1),
-            (pos.0, pos.1 - 1),
-        ],
-        Tile::SouthWest => [
-            (pos.0 + 1, pos.1),
-            (pos.0, pos.1 - 1),
-        ],
-        Tile::SouthEast => [
-            (pos.0 + 1, pos.1),
-            (pos.0, pos.1 + 1),
-        ],
-        Tile::Start => unreachable!("starting tile should have been resolved during grid creation"),
-        Tile::Ground => unreachable!("can't never be on ground"),
-    }
-}
-
-fn solve(maze: &Grid<Tile>, start: (usize, usize)) -> Vec<(usize, usize)> {
-    let first_next = get_possible_coords(maze, start);
+/// Walk the loop going through `start`, returning the visited tiles in order.
+///
+/// Returns an error if the loop is broken, i.e. a pipe leads outside the maze
+/// or onto a tile not connecting back, or if it visits a tile twice.
+fn solve(maze: &Grid<Tile>, start: (usize, usize)) -> anyhow::Result<Vec<(usize, usize)>> {
+    let first_next = get_possible_coords(maze, start)?;
     let mut cursor = first_next[0];
 
     // Coordinate we came from so we don't backtrack
@@ -118,15 +114,24 @@ fn solve(maze: &Grid<Tile>, start: (usize, usize)) -> Vec<(usize, usize)> {
     // Keep a list of coordinates we stepped on,
     // this will form a list of all points along the shape's edge
     let mut steps = vec![start];
+    let mut visited = HashSet::from([start]);
 
     loop {
-        let cursor_candidates = get_possible_coords(maze, cursor);
+        if !visited.insert(cursor) {
+            return Err(anyhow::anyhow!("loop visits {cursor:?} twice"));
+        }
+
+        let cursor_candidates = get_possible_coords(maze, cursor)?;
 
         // Decide next position, taking care we don't backtrack
         let next_cursor = if cursor_candidates[0] == last_cursor {
             cursor_candidates[1]
-        } else {
+        } else if cursor_candidates[1] == last_cursor {
             cursor_candidates[0]
+        } else {
+            return Err(anyhow::anyhow!(
+                "pipe {:?} at {cursor:?} doesn't connect back to {last_cursor:?}", maze[cursor]
+            ));
         };
 
         last_cursor = cursor;
@@ -139,7 +144,21 @@ fn solve(maze: &Grid<Tile>, start: (usize, usize)) -> Vec<(usize, usize)> {
         }
     }
 
-    steps
+    Ok(steps)
+}
+
+/// Find the tile furthest from start along the loop, halfway around `path`.
+///
+/// Returns the distance to the tile along with its coordinate.
+/// A loop of odd length has no single furthest tile and is an error,
+/// which shouldn't happen on a grid.
+fn furthest_point(path: &[(usize, usize)]) -> anyhow::Result<(usize, (usize, usize))> {
+    if !path.len().is_multiple_of(2) {
+        return Err(anyhow::anyhow!("loop has odd length {}, no single furthest point", path.len()));
+    }
+
+    let distance = path.len() / 2;
+    Ok((distance, path[distance]))
 }
 
 /// Calculate signed area of a polygon given its vertices.
@@ -160,31 +179,92 @@ fn shoelace(vertices: &[(usize, usize)]) -> isize {
     complete_sum / 2
 }
 
+/// Collect all tiles enclosed by the loop using even-odd ray casting.
+///
+/// Each row is scanned from left to right, toggling the parity whenever a
+/// north-connecting pipe (`|`, `L` or `J`) of the loop is crossed.
+/// Tiles not part of the loop are inside when the parity is odd.
+fn interior_tiles(maze: &Grid<Tile>, path: &[(usize, usize)]) -> HashSet<(usize, usize)> {
+    let path: HashSet<(usize, usize)> = path.iter().copied().collect();
+    let mut interior = HashSet::new();
+
+    for (row, tiles) in maze.iter_rows().enumerate() {
+        let mut inside = false;
+
+        for (col, tile) in tiles.enumerate() {
+            if path.contains(&(row, col)) {
+                if matches!(tile, Tile::Vertical | Tile::NorthEast | Tile::NorthWest) {
+                    inside = !inside;
+                }
+            } else if inside {
+                interior.insert((row, col));
+            }
+        }
+    }
+
+    interior
+}
+
 /// Calculate number of interior points using Pick's theorem.
 /// Area MUST have been derived from a polygon with discrete vertex coordinates.
 fn n_interior_points(area: isize, n_boundary_points: isize) -> isize {
     area.abs() - (n_boundary_points / 2) + 1
 }
 
-fn main() -> anyhow::Result<()> {
-    let input = read_input()?;
-    let (maze, start) = parse(&input);
+/// Solve the puzzle for given input.
+pub fn run(input: &str) -> anyhow::Result<Answers> {
+    let (maze, start) = timed("parse", || parse(input))?;
+    if maybe_dump(&maze, Tile::as_char)? {
+        return Ok(Answers::default());
+    }
+
+    let path = timed("silver", || solve(&maze, start))?;
+    let (distance, furthest) = furthest_point(&path)?;
+    dbgln!("furthest point {furthest:?} is {distance} steps away");
+
+    // Interior tiles are only drawn, answers count them with Pick's theorem
+    if debug_output() || svg_output().is_some() {
+        let interior = timed("interior", || interior_tiles(&maze, &path));
+
+        if debug_output() {
+            print(&maze, &HashSet::from_iter(path.iter().cloned()), &interior);
+        }
 
-    let path = solve(&maze, start);
-    let area = shoelace(&path);
+        if let Some(svg_path) = svg_output() {
+            std::fs::write(svg_path, to_svg(&maze, &path, &interior))?;
+        }
+    }
 
-    print(&maze, &HashSet::from_iter(path.iter().cloned()));
+    let part = part_filter();
+
+    Ok(Answers::new(
+        part.silver(|| distance),
+        part.gold(|| timed("gold", || {
+            let area = shoelace(&path);
+            debug_assert!(
+                (shoelace_f64(&path, |v| v as f64) - area as f64).abs() <= 0.5,
+                "integer and floating point areas disagree",
+            );
+            n_interior_points(area, path.len() as isize)
+        })),
+    ))
+}
 
-    // Distance to furthest point along the edge is edge length / 2
-    println!("Silver: {}", path.len() / 2);
-    println!("Gold:   {}", n_interior_points(area, path.len() as isize));
+fn main() -> anyhow::Result<()> {
+    let input = read_input()?;
+    run_repeated(&input, run)?.emit();
 
     Ok(())
 }
 
-fn print(maze: &Grid<Tile>, path: &HashSet<(usize, usize)>) {
+fn print(
+    maze: &Grid<Tile>,
+    path: &HashSet<(usize, usize)>,
+    interior: &HashSet<(usize, usize)>,
+) {
     const RED: &str = "\x1B[31m";
     const GREEN: &str = "\x1B[32m";
+    const YELLOW: &str = "\x1B[33m";
     const RESET: &str = "\x1B[0m";
 
     use std::io::Write;
@@ -197,6 +277,8 @@ fn print(maze: &Grid<Tile>, path: &HashSet<(usize, usize)>) {
         for (j, c) in row.enumerate() {
             if path.contains(&(i, j)) {
                 let _ = write!(lock, "{GREEN}{}", c);
+            } else if interior.contains(&(i, j)) {
+                let _ = write!(lock, "{YELLOW}█");
             } else {
                 let _ = write!(lock, "{RED}{}", c);
             }
@@ -205,7 +287,78 @@ fn print(maze: &Grid<Tile>, path: &HashSet<(usize, usize)>) {
     }
 }
 
+/// Render the maze as an SVG image.
+///
+/// Each tile is drawn as a square, rows grow downwards along the y-axis
+/// and columns to the right along the x-axis.
+fn to_svg(
+    maze: &Grid<Tile>,
+    path: &[(usize, usize)],
+    interior: &HashSet<(usize, usize)>,
+) -> String {
+    const CELL_SIZE: usize = 8;
+    const LOOP_COLOR: &str = "#2e8b57";
+    const INTERIOR_COLOR: &str = "#f0c419";
+    const GROUND_COLOR: &str = "#303030";
+
+    let path: HashSet<(usize, usize)> = path.iter().copied().collect();
+    let (rows, cols) = maze.size();
+
+    let mut svg = String::new();
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}">"#,
+        cols * CELL_SIZE,
+        rows * CELL_SIZE,
+    );
+
+    for ((row, col), _) in maze.indexed_iter() {
+        let color = if path.contains(&(row, col)) {
+            LOOP_COLOR
+        } else if interior.contains(&(row, col)) {
+            INTERIOR_COLOR
+        } else {
+            GROUND_COLOR
+        };
+
+        let _ = writeln!(
+            svg,
+            r#"  <rect x="{}" y="{}" width="{CELL_SIZE}" height="{CELL_SIZE}" fill="{color}"/>"#,
+            col * CELL_SIZE,
+            row * CELL_SIZE,
+        );
+    }
+
+    svg.push_str("</svg>\n");
+    svg
+}
+
 impl Tile {
+    /// Tiles which connect two sides.
+    const PIPES: [Tile; 6] = [
+        Tile::Vertical, Tile::Horizontal,
+        Tile::NorthEast, Tile::NorthWest, Tile::SouthWest, Tile::SouthEast,
+    ];
+
+    /// Offsets of the two sides this tile connects to,
+    /// [`None`] for ground and the unresolved start tile.
+    fn connections(&self) -> Option<[(isize, isize); 2]> {
+        match self {
+            Tile::Vertical   => Some([SOUTH, NORTH]),
+            Tile::Horizontal => Some([EAST, WEST]),
+            Tile::NorthEast  => Some([NORTH, EAST]),
+            Tile::NorthWest  => Some([NORTH, WEST]),
+            Tile::SouthWest  => Some([SOUTH, WEST]),
+            Tile::SouthEast  => Some([SOUTH, EAST]),
+            Tile::Ground | Tile::Start => None,
+        }
+    }
+
+    /// Whether this tile connects to the side at `offset`.
+    fn connects(&self, offset: (isize, isize)) -> bool {
+        self.connections().is_some_and(|sides| sides.contains(&offset))
+    }
+
     fn as_char(&self) -> char {
         match self {
             Tile::Horizontal => '─' /* '-' */,
@@ -249,3 +402,105 @@ impl Debug for Tile {
         f.write_char(self.as_char())
     }
 }
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ENCLOSED: &str = "\
+...........
+.S-------7.
+.|F-----7|.
+.||.....||.
+.||.....||.
+.|L-7.F-J|.
+.|..|.|..|.
+.L--J.L--J.
+...........
+";
+
+    #[test]
+    fn interior_matches_picks_theorem() {
+        let (maze, start) = parse(ENCLOSED).unwrap();
+        let path = solve(&maze, start).unwrap();
+        let area = shoelace(&path);
+        assert_eq!(shoelace_f64(&path, |v| v as f64), area as f64);
+
+        let interior = interior_tiles(&maze, &path);
+        assert_eq!(interior.len() as isize, n_interior_points(area, path.len() as isize));
+        assert_eq!(interior, HashSet::from([(6, 2), (6, 3), (6, 7), (6, 8)]));
+    }
+
+    #[test]
+    fn svg_maps_rows_to_y() {
+        let (maze, start) = parse(ENCLOSED).unwrap();
+        let path = solve(&maze, start).unwrap();
+        let interior = interior_tiles(&maze, &path);
+
+        let svg = to_svg(&maze, &path, &interior);
+        assert!(svg.starts_with(r#"<svg xmlns="http://www.w3.org/2000/svg" width="88" height="72">"#));
+        assert_eq!(svg.matches("<rect").count(), 9 * 11);
+        // Interior tile at row 6, column 2
+        assert!(svg.contains(r##"<rect x="16" y="48" width="8" height="8" fill="#f0c419"/>"##));
+    }
+
+    #[test]
+    fn tile_connections() {
+        let cases = [
+            ('|', Some([SOUTH, NORTH])),
+            ('-', Some([EAST, WEST])),
+            ('L', Some([NORTH, EAST])),
+            ('J', Some([NORTH, WEST])),
+            ('7', Some([SOUTH, WEST])),
+            ('F', Some([SOUTH, EAST])),
+            ('.', None),
+            ('S', None),
+        ];
+
+        for (ch, expected) in cases {
+            let tile = Tile::try_from(ch).unwrap();
+            assert_eq!(tile.connections(), expected, "{ch}");
+
+            let connected: Vec<&str> = SIDES.iter()
+                .filter(|&&(side, _)| tile.connects(side))
+                .map(|&(_, name)| name)
+                .collect();
+            assert_eq!(connected.len(), if expected.is_some() { 2 } else { 0 }, "{ch}");
+        }
+        assert_eq!(Tile::PIPES.map(|tile| tile.as_char()), ['│', '─', '└', '┘', '┐', '┌']);
+    }
+
+    #[test]
+    fn furthest_point_halfway_around() {
+        let (maze, start) = parse("..F7.\n.FJ|.\nSJ.L7\n|F--J\nLJ...\n").unwrap();
+        let path = solve(&maze, start).unwrap();
+        assert_eq!(furthest_point(&path).unwrap(), (8, (2, 4)));
+
+        assert!(furthest_point(&[(0, 0), (0, 1), (1, 1)]).is_err());
+    }
+
+    #[test]
+    fn unconnected_start_is_an_error() {
+        let err = parse("...\n.S.\n...\n").unwrap_err();
+        assert!(err.to_string().contains("found 0 (none)"), "{err}");
+    }
+
+    #[test]
+    fn broken_pipe_is_an_error() {
+        // Top right pipe leads north, out of the maze
+        let (maze, start) = parse("S-J\n|.|\nL-J\n").unwrap();
+        let err = solve(&maze, start).unwrap_err();
+        assert!(err.to_string().contains("points outside the maze"), "{err}");
+
+        // Bottom right pipe leads onto ground
+        let (maze, start) = parse("S-7.\n|.|.\nL--J\n").unwrap();
+        let err = solve(&maze, start).unwrap_err();
+        assert!(err.to_string().contains("onto ground at (1, 3)"), "{err}");
+
+        // Bottom pipe is entered from the side
+        let (maze, start) = parse("S-7.\n|.|.\nL-|.\n..J.\n").unwrap();
+        let err = solve(&maze, start).unwrap_err();
+        assert!(err.to_string().contains("doesn't connect back"), "{err}");
+    }
+}
@@ -1,11 +1,18 @@
-use advent::read_input;
+use advent::{read_input, run_repeated, part_filter, Answers, dbgln, timed, debug_output};
+use anyhow::anyhow;
 use grid::Grid;
 
+/// Largest width or height of a universe [`render_expanded`] agrees to draw.
+const MAX_RENDER_SIZE: u64 = 1000;
 
-fn parse<const GALAXY_SIZE: usize>(s: &str) -> Vec<(usize, usize)> {
+
+/// Parse galaxies, every empty row and column grows by `GALAXY_SIZE`.
+///
+/// Coordinates are `u64`, so that large expansions fit on any platform.
+fn parse<const GALAXY_SIZE: u64>(s: &str) -> anyhow::Result<Vec<(u64, u64)>> {
     let mut grid: Vec<char> = Vec::new();
 
-    let cols = s.lines().nth(0).unwrap().len();
+    let cols = s.lines().next().unwrap().len();
     for line in s.trim().lines() {
         grid.extend(line.chars())
     }
@@ -24,17 +31,17 @@ fn parse<const GALAXY_SIZE: usize>(s: &str) -> Vec<(usize, usize)> {
 
     // Find all empty column and row indices.
     // Resulting index arrays are sorted.
-    let empty_cols: Vec<usize> = grid.iter_cols()
-        .enumerate().filter_map(galaxy_filter).collect();
-    let empty_rows: Vec<usize> = grid.iter_rows()
-        .enumerate().filter_map(galaxy_filter).collect();
+    let empty_cols: Vec<u64> = grid.iter_cols()
+        .enumerate().filter_map(galaxy_filter).map(|col| col as u64).collect();
+    let empty_rows: Vec<u64> = grid.iter_rows()
+        .enumerate().filter_map(galaxy_filter).map(|row| row as u64).collect();
 
-    println!("empty cols: {empty_cols:?}, rows: {empty_rows:?}");
+    dbgln!("empty cols: {empty_cols:?}, rows: {empty_rows:?}");
 
     // Collect a list of unexpanded galaxies
     let mut galaxies = Vec::from_iter(
         grid.indexed_iter()
-            .filter_map(|(coords, &ch)| if ch == '#' { Some(coords) } else { None })
+            .filter_map(|((row, col), &ch)| if ch == '#' { Some((row as u64, col as u64)) } else { None })
     );
 
     // Expand galaxies using a "scanline".
@@ -47,49 +54,162 @@ fn parse<const GALAXY_SIZE: usize>(s: &str) -> Vec<(usize, usize)> {
     // a counter (`fix`), which is used to calculate the "true" position
     // where galaxy should be expanded.
     
-    for (empty, fix) in empty_cols.into_iter().zip(0_usize..) {
+    let overflow = || anyhow!("expanding galaxies by {GALAXY_SIZE} overflows");
+
+    for (empty, fix) in empty_cols.into_iter().zip(0_u64..) {
+        let threshold = fix.checked_mul(GALAXY_SIZE).and_then(|n| n.checked_add(empty)).ok_or_else(overflow)?;
         for galaxy in &mut galaxies {
-            if galaxy.1 >= empty + fix*GALAXY_SIZE {
-                galaxy.1 = galaxy.1 + GALAXY_SIZE;
+            if galaxy.1 >= threshold {
+                galaxy.1 = galaxy.1.checked_add(GALAXY_SIZE).ok_or_else(overflow)?;
             }
         }
     }
 
-    for (empty, fix) in empty_rows.into_iter().zip(0_usize..) {
+    for (empty, fix) in empty_rows.into_iter().zip(0_u64..) {
+        let threshold = fix.checked_mul(GALAXY_SIZE).and_then(|n| n.checked_add(empty)).ok_or_else(overflow)?;
         for galaxy in &mut galaxies {
-            if galaxy.0 >= empty + fix*GALAXY_SIZE {
-                galaxy.0 = galaxy.0 + GALAXY_SIZE;
+            if galaxy.0 >= threshold {
+                galaxy.0 = galaxy.0.checked_add(GALAXY_SIZE).ok_or_else(overflow)?;
             }
         }
     }
 
-    galaxies
+    Ok(galaxies)
 }
 
-/// Distance function measuring distance between two galaxies.
-/// In this case, L_1 norm.
-fn dist((x1, y1): (usize, usize), (x2, y2): (usize, usize)) -> usize {
-    x1.abs_diff(x2) + y1.abs_diff(y2)
+/// Draw expanded galaxies as `#` and empty space as `.`,
+/// the universe spans up to the furthest galaxy.
+fn render_expanded(galaxies: &[(u64, u64)]) -> anyhow::Result<String> {
+    let rows = galaxies.iter().map(|&(row, _)| row + 1).max().unwrap_or(0);
+    let cols = galaxies.iter().map(|&(_, col)| col + 1).max().unwrap_or(0);
+
+    if rows > MAX_RENDER_SIZE || cols > MAX_RENDER_SIZE {
+        return Err(anyhow!("expanded universe is too large to render ({rows}x{cols}, limit is {MAX_RENDER_SIZE})"));
+    }
+
+    let mut grid: Grid<char> = Grid::init(rows as usize, cols as usize, '.');
+    for &(row, col) in galaxies {
+        grid[(row as usize, col as usize)] = '#';
+    }
+
+    let mut out = String::with_capacity(grid.rows() * (grid.cols() + 1));
+    for row in grid.iter_rows() {
+        out.extend(row);
+        out.push('\n');
+    }
+    Ok(out)
 }
 
-fn main() -> anyhow::Result<()> {
-    let input = read_input()?;
-    let galaxies = parse::<999_999>(&input);
+/// Distance function measuring distance between two galaxies.
+/// In this case, L_1 norm, [`None`] if it overflows.
+fn dist((x1, y1): (u64, u64), (x2, y2): (u64, u64)) -> Option<u64> {
+    x1.abs_diff(x2).checked_add(y1.abs_diff(y2))
+}
 
-    let mut sum = 0;
+/// Sum of distances between all pairs of galaxies.
+///
+/// Returns an error if the sum overflows.
+fn sum_of_distances(galaxies: &[(u64, u64)]) -> anyhow::Result<u64> {
+    let mut sum: u64 = 0;
     for i in 0..galaxies.len() {
-        for j in i..galaxies.len() {
-            if i == j {
-                continue;
-            }
-
-            let dist = dist(galaxies[i], galaxies[j]);
-            sum += dist;
+        for j in i+1..galaxies.len() {
+            sum = dist(galaxies[i], galaxies[j])
+                .and_then(|dist| sum.checked_add(dist))
+                .ok_or_else(|| anyhow!("sum of distances overflows"))?;
             // println!("{i} -> {j} dist: {}", dist)
         }
     }
+    Ok(sum)
+}
+
+/// Solve the puzzle for given input.
+pub fn run(input: &str) -> anyhow::Result<Answers> {
+    let galaxies = timed("parse", || parse::<999_999>(input))?;
+
+    if debug_output() {
+        // Gold expansion is far too large to draw, show the silver one instead
+        match parse::<1>(input).and_then(|galaxies| render_expanded(&galaxies)) {
+            Ok(rendered) => print!("{rendered}"),
+            Err(err) => dbgln!("not rendering: {err}"),
+        }
+    }
+
+    let part = part_filter();
+
+    let sum = part.gold(|| timed("gold", || sum_of_distances(&galaxies))).transpose()?;
 
-    println!("Gold: {:?}", sum);
+    Ok(Answers::new(None::<u64>, sum))
+}
+
+fn main() -> anyhow::Result<()> {
+    let input = read_input()?;
+    run_repeated(&input, run)?.emit();
 
     Ok(())
 }
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+...#......
+.......#..
+#.........
+..........
+......#...
+.#........
+.........#
+..........
+.......#..
+#...#.....
+";
+
+    #[test]
+    fn render_silver_expansion() {
+        let expanded = "\
+....#........
+.........#...
+#............
+.............
+.............
+........#....
+.#...........
+............#
+.............
+.............
+.........#...
+#....#.......
+";
+        assert_eq!(render_expanded(&parse::<1>(EXAMPLE).unwrap()).unwrap(), expanded);
+    }
+
+    #[test]
+    fn render_refuses_gold_expansion() {
+        assert!(render_expanded(&parse::<999_999>(EXAMPLE).unwrap()).is_err());
+    }
+
+    #[test]
+    fn large_expansion() {
+        // Two empty columns between galaxies, one empty row below them
+        const SIZE: u64 = 1 << 40;
+        let galaxies = parse::<SIZE>("#..#\n....\n").unwrap();
+        assert_eq!(galaxies, [(0, 0), (0, 3 + 2 * SIZE)]);
+        assert_eq!(dist(galaxies[0], galaxies[1]), Some(3 + 2 * SIZE));
+        assert_eq!(sum_of_distances(&galaxies).unwrap(), 3 + 2 * SIZE);
+
+        let err = parse::<{ u64::MAX / 2 }>("#..#\n").unwrap_err();
+        assert!(err.to_string().contains("overflows"), "{err}");
+
+        // Expansion fits, but distances don't
+        const HALF: u64 = 1 << 63;
+        assert_eq!(dist((0, 0), (HALF, HALF)), None);
+        let galaxies = parse::<HALF>("#.#\n").unwrap();
+        assert_eq!(galaxies, [(0, 0), (0, HALF + 2)]);
+        assert_eq!(sum_of_distances(&galaxies).unwrap(), HALF + 2);
+        let galaxies = parse::<{ HALF / 2 }>("#.#.#\n").unwrap();
+        let err = sum_of_distances(&galaxies).unwrap_err();
+        assert_eq!(err.to_string(), "sum of distances overflows");
+    }
+}
@@ -1,9 +1,9 @@
 use std::{str::FromStr, fmt::Write, collections::HashMap};
 
-use advent::read_input;
+use advent::{read_input, run_repeated, part_filter, Answers, dbgln, progress, timed};
 use anyhow::anyhow;
 
-#[derive(Clone, Copy, PartialEq)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
 enum Spring {
     Operational,
     Damaged,
@@ -27,16 +27,113 @@ struct Puzzle {
     groups: Vec<usize>,
 }
 
+/// Most unknown springs [`Puzzle::enumerate`] agrees to go through.
+const MAX_ENUMERATED_UNKNOWNS: usize = 20;
+
+/// Combination counts of already solved puzzles, keyed by `(springs, groups)`.
+type Memo = HashMap<(Vec<Spring>, Vec<usize>), usize>;
 
 impl Puzzle {
+    /// Count valid combinations, reusing results of identical puzzles from `memo` if given.
+    fn combinations(&self, memo: Option<&mut Memo>) -> usize {
+        let Some(memo) = memo else {
+            return self.count_combinations();
+        };
+
+        let key = (self.springs.clone(), self.groups.clone());
+        if let Some(&cached) = memo.get(&key) {
+            return cached;
+        }
+
+        let count = self.count_combinations();
+        memo.insert(key, count);
+        count
+    }
+
+    /// Count valid combinations with some springs forced to a given state,
+    /// as `(index, spring)` pairs.
+    ///
+    /// Forcing a spring which is already known to a different state yields 0.
+    #[cfg_attr(not(test), allow(dead_code))]
+    fn combinations_with_constraint(&self, forced: &[(usize, Spring)]) -> usize {
+        let mut springs = self.springs.clone();
+        for &(i, spring) in forced {
+            match springs[i] {
+                Spring::Unknown => springs[i] = spring,
+                known if spring != Spring::Unknown && known != spring => return 0,
+                _ => {},
+            }
+        }
+
+        let constrained = Self { springs, groups: self.groups.clone() };
+        constrained.count_combinations()
+    }
+
     /// Count valid combinations
+    fn count_combinations(&self) -> usize {
+        // Rows without unknowns have a single arrangement, check it directly
+        if !self.springs.contains(&Spring::Unknown) {
+            return self.matches_groups() as usize;
+        }
+
+        self.count_combinations_dp()
+    }
+
+    /// List all valid arrangements, with every unknown spring resolved.
+    ///
+    /// Both states of each unknown spring are tried, so rows with more than
+    /// [`MAX_ENUMERATED_UNKNOWNS`] unknowns are refused.
+    #[cfg_attr(not(test), allow(dead_code))]
+    fn enumerate(&self) -> anyhow::Result<Vec<Vec<Spring>>> {
+        fn backtrack(puzzle: &mut Puzzle, unknowns: &[usize], out: &mut Vec<Vec<Spring>>) {
+            let Some((&i, rest)) = unknowns.split_first() else {
+                if puzzle.matches_groups() {
+                    out.push(puzzle.springs.clone());
+                }
+                return;
+            };
+
+            for spring in [Spring::Operational, Spring::Damaged] {
+                puzzle.springs[i] = spring;
+                backtrack(puzzle, rest, out);
+            }
+        }
+
+        let unknowns: Vec<usize> = self.springs.iter()
+            .enumerate()
+            .filter_map(|(i, &spring)| (spring == Spring::Unknown).then_some(i))
+            .collect();
+
+        if unknowns.len() > MAX_ENUMERATED_UNKNOWNS {
+            return Err(anyhow!(
+                "too many unknown springs to enumerate ({}, limit is {MAX_ENUMERATED_UNKNOWNS})",
+                unknowns.len(),
+            ));
+        }
+
+        let mut puzzle = Self { springs: self.springs.clone(), groups: self.groups.clone() };
+        let mut out = Vec::new();
+        backtrack(&mut puzzle, &unknowns, &mut out);
+        Ok(out)
+    }
+
+    /// Whether damaged springs form exactly the expected groups,
+    /// unknown springs are treated as operational.
+    fn matches_groups(&self) -> bool {
+        self.springs.split(|&spring| spring != Spring::Damaged)
+            .filter(|group| !group.is_empty())
+            .map(|group| group.len())
+            .eq(self.groups.iter().copied())
+    }
+
+    /// Count valid combinations by going through all springs
     /// 
     /// Python implementation by **rrutkows**:
     /// https://github.com/rrutkows/aoc_py/blob/1efbd74961499edcf68b0749f39ec8b13853db8a/2023/d12.py
     /// 
     /// Idea by **KayZGames**:
     /// https://www.reddit.com/r/adventofcode/comments/18hbjdi/2023_day_12_part_2_this_image_helped_a_few_people/
-    fn combinations(&self) -> usize {
+    fn count_combinations_dp(&self) -> usize {
         // Map to keep track of all permutation counts there is.
         // Key here is (group_idx, group_amount) and value is the number of permutations.
         //
@@ -73,15 +170,16 @@ impl Puzzle {
                     // 2. 
                 }
 
-                if spring != Spring::Operational {
-                    if group_idx < self.groups.len() && group_amount < self.groups[group_idx] {
-                        // There is still space in this group, increase amount
-                        next.push((group_idx, group_amount + 1, perm_count))
-                    }
-                    // Impossible cases dropped:
-                    // 1. group would overfill
-                    // 2. trying to fill nonexisting group
+                if spring != Spring::Operational
+                    && group_idx < self.groups.len()
+                    && group_amount < self.groups[group_idx]
+                {
+                    // There is still space in this group, increase amount
+                    next.push((group_idx, group_amount + 1, perm_count))
                 }
+                // Impossible cases dropped:
+                // 1. group would overfill
+                // 2. trying to fill nonexisting group
             }
 
             // Reset permutations from last iteration and sum all
@@ -119,18 +217,67 @@ impl Puzzle {
     }
 }
 
-fn main() -> anyhow::Result<()> {
-    let input = read_input()?;
-    let puzzles = parse(input.lines())?;
+/// Sum combinations of all puzzles.
+#[cfg(feature = "parallel")]
+fn total_combinations(puzzles: &[Puzzle]) -> usize {
+    total_combinations_parallel(puzzles)
+}
+
+/// Sum combinations of all puzzles.
+#[cfg(not(feature = "parallel"))]
+fn total_combinations(puzzles: &[Puzzle]) -> usize {
+    total_combinations_serial(puzzles)
+}
+
+/// Sum combinations of all puzzles one after another.
+#[cfg(any(test, not(feature = "parallel")))]
+fn total_combinations_serial(puzzles: &[Puzzle]) -> usize {
+    // Inputs may have duplicate rows, share results between them
+    let mut memo = Memo::new();
 
     let mut sum = 0;
-    for puzzle in puzzles {
-        println!("{}", puzzle);
+    for (done, puzzle) in puzzles.iter().enumerate() {
+        let combinations = puzzle.combinations(Some(&mut memo));
+        dbgln!("{puzzle}\n  combinations: {combinations}");
+        progress(done + 1, puzzles.len());
 
-        sum += dbg!(puzzle.combinations());
+        sum += combinations;
     }
+    sum
+}
+
+/// Each puzzle is solved independently, duplicate rows aren't shared.
+#[cfg(feature = "parallel")]
+fn total_combinations_parallel(puzzles: &[Puzzle]) -> usize {
+    use rayon::prelude::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    let done = AtomicUsize::new(0);
+    puzzles.par_iter()
+        .map(|puzzle| {
+            let combinations = puzzle.combinations(None);
+            dbgln!("{puzzle}\n  combinations: {combinations}");
+            progress(done.fetch_add(1, Ordering::Relaxed) + 1, puzzles.len());
+            combinations
+        })
+        .sum()
+}
+
+/// Solve the puzzle for given input.
+pub fn run(input: &str) -> anyhow::Result<Answers> {
+    let puzzles = timed("parse", || parse(input.lines()))?;
+
+    let part = part_filter();
+
+    Ok(Answers::new(
+        None::<usize>,
+        part.gold(|| timed("gold", || total_combinations(&puzzles))),
+    ))
+}
 
-    println!("Gold: {sum}");
+fn main() -> anyhow::Result<()> {
+    let input = read_input()?;
+    run_repeated(&input, run)?.emit();
 
     Ok(())
 }
@@ -144,7 +291,7 @@ impl FromStr for Puzzle {
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let (springs, damages) = s.split_once(' ').ok_or_else(|| anyhow!("invalid puzzle line"))?;
         let springs = springs.chars()
-            .map(|ch| Spring::from_char(ch))
+            .map(Spring::from_char)
             .collect::<Result<Vec<_>, _>>()?;
 
         // oof
@@ -180,3 +327,115 @@ impl std::fmt::Display for Puzzle {
         write!(f, "{:?} {:?}", self.springs, self.groups)
     }
 }
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+???.### 1,1,3
+.??..??...?##. 1,1,3
+?#?#?#?#?#?#?#? 1,3,1,6
+????.#...#... 4,1,1
+????.######..#####. 1,6,5
+?###???????? 3,2,1
+";
+
+    /// Puzzle of a row as it is, without unfolding it.
+    fn folded(springs: &str, groups: &[usize]) -> Puzzle {
+        Puzzle {
+            springs: springs.chars().map(|ch| Spring::from_char(ch).unwrap()).collect(),
+            groups: groups.to_vec(),
+        }
+    }
+
+    #[test]
+    fn unfolded_combinations() {
+        let puzzles = parse(EXAMPLE.lines()).unwrap();
+        let counts: Vec<usize> = puzzles.iter().map(|puzzle| puzzle.combinations(None)).collect();
+
+        assert_eq!(counts, &[1, 16384, 1, 16, 2500, 506250]);
+    }
+
+    #[test]
+    fn concrete_rows() {
+        let cases = [
+            (folded("#.#.###", &[1, 1, 3]), 1),
+            (folded("..##..#.", &[2, 1]), 1),
+            (folded("#.#.###", &[1, 1, 2]), 0),
+            (folded("#.#.###", &[1, 1]), 0),
+            (folded("##.#.###", &[1, 1, 3]), 0),
+        ];
+
+        for (puzzle, expected) in cases {
+            assert_eq!(puzzle.count_combinations(), expected, "{puzzle}");
+            assert_eq!(puzzle.count_combinations_dp(), expected, "{puzzle}");
+        }
+    }
+
+    #[test]
+    fn enumerated_arrangements() {
+        let cases = [
+            folded("???.###", &[1, 1, 3]),
+            folded(".??..??...?##.", &[1, 1, 3]),
+            folded("?#?#?#?#?#?#?#?", &[1, 3, 1, 6]),
+            folded("????.#...#...", &[4, 1, 1]),
+            folded("????.######..#####.", &[1, 6, 5]),
+            folded("?###????????", &[3, 2, 1]),
+            folded("#.#.###", &[1, 1, 2]),
+        ];
+
+        for puzzle in cases {
+            let arrangements = puzzle.enumerate().unwrap();
+            assert_eq!(arrangements.len(), puzzle.combinations(None), "{puzzle}");
+            assert!(arrangements.iter().all(|springs| !springs.contains(&Spring::Unknown)));
+        }
+
+        let arrangements = folded("???.###", &[1, 1, 3]).enumerate().unwrap();
+        assert_eq!(format!("{arrangements:?}"), "[[#, ., #, ., #, #, #]]");
+
+        // Unfolded rows are mostly too large
+        let puzzles = parse(EXAMPLE.lines()).unwrap();
+        assert_eq!(puzzles[0].enumerate().unwrap().len(), 1);
+        assert!(puzzles[1].enumerate().is_err());
+    }
+
+    #[test]
+    fn forced_springs() {
+        let puzzle: Puzzle = ".??..??...?##. 1,1,3".parse().unwrap();
+        let total = puzzle.combinations(None);
+
+        // Vacuous constraints
+        assert_eq!(puzzle.combinations_with_constraint(&[]), total);
+        assert_eq!(puzzle.combinations_with_constraint(&[(0, Spring::Operational), (1, Spring::Unknown)]), total);
+
+        // Unknown spring is either damaged or operational
+        let damaged = puzzle.combinations_with_constraint(&[(1, Spring::Damaged)]);
+        let operational = puzzle.combinations_with_constraint(&[(1, Spring::Operational)]);
+        assert!(damaged > 0 && operational > 0);
+        assert_eq!(damaged + operational, total);
+
+        // Contradicts a literal `.`
+        assert_eq!(puzzle.combinations_with_constraint(&[(0, Spring::Damaged)]), 0);
+    }
+
+    #[test]
+    fn memo_shares_duplicate_rows() {
+        let input = format!("{EXAMPLE}{EXAMPLE}");
+        let puzzles = parse(input.lines()).unwrap();
+
+        let mut memo = Memo::new();
+        let sum: usize = puzzles.iter().map(|puzzle| puzzle.combinations(Some(&mut memo))).sum();
+
+        assert_eq!(sum, 2 * 525152);
+        assert_eq!(memo.len(), 6);
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn parallel_matches_serial() {
+        let puzzles = parse(EXAMPLE.lines()).unwrap();
+        assert_eq!(total_combinations_parallel(&puzzles), total_combinations_serial(&puzzles));
+    }
+}
@@ -1,66 +1,159 @@
-use advent::read_input;
+use std::collections::HashMap;
+
+use advent::{read_input, run_repeated, part_filter, Answers, debug_output, dbgln, dump_grid, find_mirrors, maybe_dump, mirror_differences, timed, transpose};
+use anyhow::anyhow;
 use grid::Grid;
 
-#[derive(Clone, Copy, PartialEq, Eq)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
 enum Tile { Ash, Rock }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 enum Reflection {
     Column(usize),
     Row(usize),
 }
 
-fn solve(pattern: &Grid<Tile>) -> Reflection {
-    let (rows, cols) = pattern.size();
+/// Reflections of already solved patterns, keyed by their column count and tiles.
+///
+/// Orientation is part of the key, as flipping a pattern moves its mirrors.
+type Memo = HashMap<(usize, Vec<Tile>), Reflection>;
 
-    // Look at all neighboring columns and rows and check if we cant start mirror there
-    // Returned `pivot`s here are already corrected for the 1-based indexing
+/// Weights of columns and rows left of or above a reflection line.
+const COLUMN_WEIGHT: usize = 1;
+const ROW_WEIGHT: usize = 100;
 
-    for (i, j) in (0..cols-1).zip(1..cols) {
-        if let Some((pivot, true)) = check_expanding(
-            |col| pattern.iter_col(col),
-            i,
-            j,
-            pattern.cols()-1,
-        ) {
-            println!("found mirror at [COLUMN] pivot: {pivot:?} (smudged)");
-            return Reflection::Column(pivot);
+/// Score a reflection line by the number of columns or rows before it.
+fn summarize(reflection: Reflection, col_weight: usize, row_weight: usize) -> usize {
+    match reflection {
+        Reflection::Column(n) => n*col_weight,
+        Reflection::Row(n)    => n*row_weight,
+    }
+}
+
+/// Find the reflection line with exactly `smudges` differences.
+///
+/// Lines with a different amount of smudges are never picked, so the smudged
+/// line (gold) is always distinct from the clean one (silver).
+///
+/// Returns an error naming the closest lines if there's no such line.
+fn solve(pattern: &Grid<Tile>, smudges: usize) -> anyhow::Result<Reflection> {
+    let Some(reflection) = find_reflections(pattern, smudges).into_iter().next() else {
+        let (closest, differences) = closest_reflections(pattern);
+        if closest.is_empty() {
+            return Err(anyhow!("pattern is too small to have a mirror"));
         }
+
+        return Err(anyhow!(
+            "no mirror with {smudges} smudges, closest are {closest:?} with {differences} smudges",
+        ));
+    };
+
+    dbgln!("found mirror at {reflection:?} ({smudges} smudges)");
+    Ok(reflection)
+}
+
+/// Similar to [`solve`], but reuses reflections of identical patterns from `memo` if given.
+///
+/// Patterns are told apart by their tiles only, so the memo is only valid
+/// for a single amount of `smudges`.
+fn solve_memoized(pattern: &Grid<Tile>, smudges: usize, memo: Option<&mut Memo>) -> anyhow::Result<Reflection> {
+    let Some(memo) = memo else {
+        return solve(pattern, smudges);
+    };
+
+    let key = (pattern.cols(), pattern.flatten().clone());
+    if let Some(&cached) = memo.get(&key) {
+        return Ok(cached);
     }
 
-    for (i, j) in (0..rows-1).zip(1..rows) {
-        if let Some((pivot, true)) = check_expanding(
-            |row| pattern.iter_row(row),
-            i,
-            j,
-            pattern.rows()-1
-        ) {
-            println!("found mirror at [ROW] pivot: {pivot:?} (smudged)");
-            return Reflection::Row(pivot);
+    let reflection = solve(pattern, smudges)?;
+    memo.insert(key, reflection);
+    Ok(reflection)
+}
+
+/// Find reflection lines with the fewest differences, along with the amount of differences.
+fn closest_reflections(pattern: &Grid<Tile>) -> (Vec<Reflection>, usize) {
+    let transposed = transpose(pattern);
+    let differences = |pattern: &Grid<Tile>, pivot| {
+        mirror_differences(|row| pattern.iter_row(row), pivot, pattern.rows())
+    };
+
+    let columns = (1..transposed.rows()).map(|pivot| (Reflection::Column(pivot), differences(&transposed, pivot)));
+    let rows = (1..pattern.rows()).map(|pivot| (Reflection::Row(pivot), differences(pattern, pivot)));
+
+    let mut closest = Vec::new();
+    let mut min = usize::MAX;
+    for (reflection, differences) in columns.chain(rows) {
+        if differences < min {
+            closest.clear();
+            min = differences;
+        }
+        if differences == min {
+            closest.push(reflection);
         }
     }
 
-    unreachable!("patterns should have exactly one mirror");
+    (closest, min)
 }
 
-fn main() -> anyhow::Result<()> {
-    let input = read_input()?;
-    let patterns = parse_patterns(&input);
-
-    let mut sum: usize = 0;
-    for pattern in patterns {
-        print(&pattern);
-        match solve(&pattern) {
-            Reflection::Column(n) => sum += n,
-            Reflection::Row(n)    => sum += n*100,
+/// Find all column and row reflection lines with exactly `smudges` differences.
+fn find_reflections(pattern: &Grid<Tile>, smudges: usize) -> Vec<Reflection> {
+    // Column reflections are row reflections of the transposed pattern
+    let transposed = transpose(pattern);
+    let columns = find_row_mirrors(&transposed, smudges).map(Reflection::Column);
+    let rows = find_row_mirrors(pattern, smudges).map(Reflection::Row);
+
+    columns.chain(rows).collect()
+}
+
+/// Find mirrors between two rows of the pattern with exactly `smudges` differences.
+///
+/// Mirrors are given as the number of rows above them.
+fn find_row_mirrors(pattern: &Grid<Tile>, smudges: usize) -> impl Iterator<Item = usize> + '_ {
+    find_mirrors(move |row| pattern.iter_row(row), pattern.rows(), smudges)
+}
+
+/// Solve the puzzle for given input.
+pub fn run(input: &str) -> anyhow::Result<Answers> {
+    let patterns = timed("parse", || parse_patterns(input));
+    if dump_grid() {
+        for pattern in &patterns {
+            maybe_dump(pattern, |&tile| tile.to_char())?;
         }
+        return Ok(Answers::default());
     }
 
-    println!("Gold: {}", sum);
+    if debug_output() {
+        for pattern in &patterns {
+            print(pattern);
+        }
+    }
+
+    let part = part_filter();
+
+    Ok(Answers::new(
+        part.silver(|| timed("silver", || score(&patterns, 0, COLUMN_WEIGHT, ROW_WEIGHT))).transpose()?,
+        part.gold(|| timed("gold", || score(&patterns, 1, COLUMN_WEIGHT, ROW_WEIGHT))).transpose()?,
+    ))
+}
+
+fn main() -> anyhow::Result<()> {
+    let input = read_input()?;
+    run_repeated(&input, run)?.emit();
 
     Ok(())
 }
 
+/// Sum scores of reflection lines with `smudges` differences in all patterns.
+///
+/// Duplicate patterns are only solved once.
+fn score(patterns: &[Grid<Tile>], smudges: usize, col_weight: usize, row_weight: usize) -> anyhow::Result<usize> {
+    let mut memo = Memo::new();
+    patterns.iter()
+        .map(|pattern| Ok(summarize(solve_memoized(pattern, smudges, Some(&mut memo))?, col_weight, row_weight)))
+        .sum()
+}
+
 fn parse_patterns(s: &str) -> Vec<Grid<Tile>> {
     let mut out: Vec<Grid<Tile>> = Vec::new();
     let mut builder: Vec<Tile> = Vec::new();
@@ -84,94 +177,28 @@ fn parse_patterns(s: &str) -> Vec<Grid<Tile>> {
 }
 
 
-/// Check for reflection by iteratively expanding two indices.
-/// 
-/// Example how `i` and `j` move, each column (or similarly a row) must match.
-/// in order for the iterator to continue. If either `i` or `j` reach array 
-/// bounds, the array has a mirror which's pivot is at the original `i` index.
-/// ```not_rust
-///     ij
-/// #.##..##.
-/// ---------
-///    i  j
-/// #.##..##.
-/// ---------
-///   i    j
-/// #.##..##.
-/// ```
-fn check_expanding<F, I>(
-    source: F,
-    mut i: usize,
-    mut j: usize,
-    max_j: usize,
-) -> Option<(usize, bool)>
-where
-    F: Fn(usize) -> I,
-    I: Iterator,
-    I::Item: PartialEq,
-{
-    let pivot = i;
-    let mut smudge_cleaned = false;
-
-    let res = loop {
-        // Check for reflection between arrays given by i and j
-        
-        // Count how many tiles differ between arrays
-        let differing = source(i).zip(source(j))
-            .filter(|(el_i, el_j)| el_i != el_j)
-            .count();
-        
-        // If there is only one difference and we haven't cleaned any smudges
-        // yet, we can clean this one.
-        if !smudge_cleaned && differing == 1 {
-            smudge_cleaned = true;
-            // Continue on as normal
-        } else if differing != 0 {
-            // Arrays cannot be cleaned because there's either
-            // - too much to clean
-            // - smudge has already been cleaned
-            // therefore, this is not a mirror
-            break None
-        }
-
-        // If we would go out-of-bounds next iteration, this is a mirror
-        if i == 0 || j == max_j {
-            break Some((pivot + 1, smudge_cleaned))
-        }
-
-        // Otherwise, expand the search and repeat
-        i -= 1;
-        j += 1;
-    };
-
-    res
-}
-
 fn print(pattern: &Grid<Tile>) {
     use std::io::Write;
     let mut lock = std::io::stdout().lock();
 
     let cols = pattern.cols();
 
-    let _ = write!(lock, "╭{:─<cols$}╮\n", "");
+    let _ = writeln!(lock, "╭{:─<cols$}╮", "");
     for row in pattern.iter_rows() {
         let _ = write!(lock, "│");
         for ch in row {
             let _ = write!(lock, "{}", ch);
         }
         let _ = write!(lock, "│");
-        let _ = write!(lock, "\n");
+        let _ = writeln!(lock);
     }
-    let _ = write!(lock, "╰{:─<cols$}╯\n", "");
+    let _ = writeln!(lock, "╰{:─<cols$}╯", "");
 }
 
 impl std::fmt::Debug for Tile {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         use std::fmt::Write;
-        match self {
-            Tile::Ash => f.write_char('.'),
-            Tile::Rock => f.write_char('#'),
-        }
+        f.write_char(self.to_char())
     }
 }
 
@@ -193,4 +220,124 @@ impl Tile {
             _   => None
         }
     }
+
+    /// Character used for this tile in puzzle input.
+    fn to_char(self) -> char {
+        match self {
+            Self::Ash => '.',
+            Self::Rock => '#',
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+#.##..##.
+..#.##.#.
+##......#
+##......#
+..#.##.#.
+..##..##.
+#.#.##.#.
+
+#...##..#
+#....#..#
+..##..###
+#####.##.
+#####.##.
+..##..###
+#....#..#
+";
+
+    /// Parse patterns and assert they render back to `input`.
+    fn roundtrip(input: &str) {
+        let rendered: Vec<String> = parse_patterns(input).iter()
+            .map(|pattern| pattern.iter_rows()
+                .map(|row| row.map(|tile| tile.to_char()).collect::<String>())
+                .collect::<Vec<_>>()
+                .join("\n"))
+            .collect();
+
+        assert_eq!(rendered.join("\n\n"), input.trim());
+    }
+
+    #[test]
+    fn patterns_roundtrip() {
+        roundtrip(EXAMPLE);
+    }
+
+    #[test]
+    fn example_summaries() {
+        let patterns = parse_patterns(EXAMPLE);
+        assert_eq!(score(&patterns, 0, COLUMN_WEIGHT, ROW_WEIGHT).unwrap(), 405);
+        assert_eq!(score(&patterns, 1, COLUMN_WEIGHT, ROW_WEIGHT).unwrap(), 400);
+
+        // Silver has a column line at 5 and a row line at 4
+        assert_eq!(score(&patterns, 0, 1, 0).unwrap(), 5);
+        assert_eq!(score(&patterns, 0, 0, 1).unwrap(), 4);
+    }
+
+    #[test]
+    fn weighted_summary() {
+        assert_eq!(summarize(Reflection::Column(3), 1, 100), 3);
+        assert_eq!(summarize(Reflection::Row(3), 1, 100), 300);
+        assert_eq!(summarize(Reflection::Column(3), 7, 0), 21);
+        assert_eq!(summarize(Reflection::Row(3), 0, 7), 21);
+    }
+
+    #[test]
+    fn all_reflections() {
+        let pattern = &parse_patterns("##..##\n##..##\n")[0];
+
+        assert_eq!(
+            find_reflections(pattern, 0),
+            &[Reflection::Column(1), Reflection::Column(3), Reflection::Column(5), Reflection::Row(1)],
+        );
+        assert_eq!(solve(pattern, 0).unwrap(), Reflection::Column(1));
+    }
+
+    #[test]
+    fn no_reflection() {
+        let pattern = &parse_patterns("#.#\n..#\n")[0];
+        assert_eq!(closest_reflections(pattern), (vec![Reflection::Column(1), Reflection::Row(1)], 1));
+
+        let err = solve(pattern, 0).unwrap_err();
+        assert_eq!(err.to_string(), "no mirror with 0 smudges, closest are [Column(1), Row(1)] with 1 smudges");
+        assert_eq!(solve(pattern, 1).unwrap(), Reflection::Column(1));
+
+        let err = solve(&parse_patterns("#\n")[0], 0).unwrap_err();
+        assert_eq!(err.to_string(), "pattern is too small to have a mirror");
+    }
+
+    #[test]
+    fn duplicate_patterns() {
+        let first = EXAMPLE.split("\n\n").next().unwrap();
+        let patterns = parse_patterns(&format!("{EXAMPLE}\n{first}\n"));
+        assert_eq!(patterns.len(), 3);
+
+        for smudges in [0, 1] {
+            let mut memo = Memo::new();
+            let memoized: Vec<Reflection> = patterns.iter()
+                .map(|pattern| solve_memoized(pattern, smudges, Some(&mut memo)).unwrap())
+                .collect();
+            let fresh: Vec<Reflection> = patterns.iter()
+                .map(|pattern| solve(pattern, smudges).unwrap())
+                .collect();
+
+            assert_eq!(memoized, fresh);
+            assert_eq!(memo.len(), 2);
+
+            // Same tiles in another orientation are a different pattern
+            let transposed = transpose(&patterns[0]);
+            assert_eq!(
+                solve_memoized(&transposed, smudges, Some(&mut memo)).unwrap(),
+                solve(&transposed, smudges).unwrap(),
+            );
+            assert_eq!(memo.len(), 3);
+        }
+    }
 }
@@ -1,6 +1,6 @@
 use std::collections::HashMap;
 
-use advent::read_input;
+use advent::{read_input, run_repeated, part_filter, ascii_output, Answers, debug_output, dbgln, maybe_dump, timed, frames_output, rotate_cw};
 use grid::Grid;
 
 #[derive(Clone, Copy, PartialEq, Eq, Hash)]
@@ -19,6 +19,16 @@ impl Direction {
             Self::East  => Self::North,
         }
     }
+
+    /// Number of clockwise rotations needed to turn this side of the board north.
+    fn turns_to_north(self) -> usize {
+        match self {
+            Self::North => 0,
+            Self::West  => 1,
+            Self::South => 2,
+            Self::East  => 3,
+        }
+    }
 }
 
 /// Swaps two elements of given grid.
@@ -33,52 +43,76 @@ unsafe fn swap(grid: &mut Grid<Tile>, x: (usize, usize), y: (usize, usize)) {
     }
 }
 
-/// Calculate next position based on `direction`.
-fn next_position(pos: (usize, usize), direction: Direction) -> Option<(usize, usize)> {
-    Some(match direction {
-        Direction::North => (pos.0.checked_sub(1)?, pos.1),
-        Direction::West  => (pos.0, pos.1.checked_sub(1)?),
-        Direction::South => (pos.0 + 1, pos.1),
-        Direction::East  => (pos.0, pos.1 + 1),
-    })
-}
-
 struct Puzzle {
     puzzle_main: Grid<Tile>,
     puzzle_temp: Grid<Tile>,
     seen: HashMap<Vec<Tile>, usize>,
     cycle_length: usize,
     loads: Vec<usize>,
+    /// Whether board state should be recorded after each quarter-tilt
+    record_frames: bool,
+    frames: Vec<Grid<Tile>>,
 }
 
 impl Puzzle {
-    fn new(puzzle: Grid<Tile>) -> Self {
+    fn new(puzzle: Grid<Tile>, record_frames: bool) -> Self {
         Self {
             puzzle_main: puzzle.clone(),
             puzzle_temp: puzzle,
             seen: HashMap::new(),
             cycle_length: 0,
             loads: Vec::new(),
+            record_frames,
+            frames: Vec::new(),
         }
     }
 
+    /// Board parsed from puzzle input, without recording frames.
+    #[cfg(test)]
+    fn from_input(input: &str) -> Self {
+        Self::new(parse(input), false)
+    }
+
     fn get(&self) -> &Grid<Tile> {
         &self.puzzle_main
     }
 
-    fn print(&self) {
-        print_puzzle(&self.puzzle_main).unwrap();
+    /// Board states after each quarter-tilt, if recording was enabled.
+    fn frames(&self) -> &[Grid<Tile>] {
+        &self.frames
+    }
+
+    fn print(&self, mode: RenderMode) {
+        print_puzzle(&self.puzzle_main, mode).unwrap();
     }
 
-    /// Ticks boulders on the board to some [`Direction`].
+    /// Tilts the board towards some [`Direction`] until all boulders have settled.
+    ///
+    /// Board is rotated so that `direction` faces north, tilted north and rotated back.
+    fn tilt(&mut self, direction: Direction) {
+        let turns = direction.turns_to_north();
+        for _ in 0..turns {
+            self.puzzle_main = rotate_cw(&self.puzzle_main);
+        }
+
+        // Rotated board may have different dimensions, keep `temp` in sync
+        self.puzzle_main.clone_into(&mut self.puzzle_temp);
+        while self.tick() > 0 {}
+
+        for _ in 0..(4 - turns) % 4 {
+            self.puzzle_main = rotate_cw(&self.puzzle_main);
+        }
+    }
+
+    /// Ticks boulders on the board one step north.
     /// 
     /// Returns how many boulders were moved.
-    fn tick(&mut self, direction: Direction) -> usize {
+    fn tick(&mut self) -> usize {
         let mut tiles_moved = 0;
 
         for ((row, col), _) in self.puzzle_main.indexed_iter().filter(|(_, tile)| **tile == Tile::Round) {
             // Look north for some space
-            let Some((next_row, next_col)) = next_position((row, col), direction) else {
+            let Some((next_row, next_col)) = row.checked_sub(1).map(|next_row| (next_row, col)) else {
                 continue; // Out of bounds, continue
             };
             
@@ -115,56 +149,81 @@ impl Iterator for &mut Puzzle {
     fn next(&mut self) -> Option<Self::Item> {
         let mut direction = Direction::North;
         loop {
-            let tiles_moved = self.tick(direction);
-
-            if tiles_moved == 0 {
-                direction = direction.next();
-
-                // We've moved back to start, cycle is complete
-                if direction == Direction::North {
-                    let load = calculate_load(self.get());
-                    // Check if we have seen this before
-                    // sadly, we can't get immutable ref to the underlying vec
-                    // so we have to clone the whole grid
-                    let oof = self.puzzle_main.clone().into_vec();
-
-                    self.cycle_length += 1;
-                    self.loads.push(load);
-                    if let Some(&cached_cycle) = self.seen.get(&oof) {
-                        println!("seen this grid before! at cycle {}, loop length {}", cached_cycle, self.cycle_length - cached_cycle);
-                        return Some((load, cached_cycle, true));
-                    } else {
-                        self.seen.insert(oof, self.cycle_length);
-                    }
-
-                    return Some((load, self.cycle_length, false));
+            self.tilt(direction);
+
+            // Board has settled to this direction
+            if self.record_frames {
+                self.frames.push(self.puzzle_main.clone());
+            }
+
+            direction = direction.next();
+
+            // We've moved back to start, cycle is complete
+            if direction == Direction::North {
+                let load = calculate_load(self.get());
+                // Check if we have seen this before
+                // sadly, we can't get immutable ref to the underlying vec
+                // so we have to clone the whole grid
+                let oof = self.puzzle_main.clone().into_vec();
+
+                self.cycle_length += 1;
+                self.loads.push(load);
+                if let Some(&cached_cycle) = self.seen.get(&oof) {
+                    dbgln!("seen this grid before! at cycle {}, loop length {}", cached_cycle, self.cycle_length - cached_cycle);
+                    return Some((load, cached_cycle, true));
+                } else {
+                    self.seen.insert(oof, self.cycle_length);
                 }
+
+                return Some((load, self.cycle_length, false));
             }
         }
     }
 }
 
-fn main() -> anyhow::Result<()> {
-    let input = read_input()?;
-    let mut puzzle = Puzzle::new(parse(&input));
+/// Solve the puzzle for given input.
+pub fn run(input: &str) -> anyhow::Result<Answers> {
+    let grid = timed("parse", || parse(input));
+    if maybe_dump(&grid, |&tile| tile.to_char())? {
+        return Ok(Answers::default());
+    }
+    let mut puzzle = Puzzle::new(grid, frames_output().is_some());
 
-    puzzle.print();
+    if debug_output() {
+        puzzle.print(if ascii_output() { RenderMode::Ascii } else { RenderMode::Unicode });
+    }
+
+    let part = part_filter();
 
-    let mut loop_start = 0;
-    let mut loop_length = 0;
+    let gold = part.gold(|| -> anyhow::Result<usize> {
+        let (loop_start, loop_length) = timed("gold", || {
+            for ((_, int_cycle, cycle_found), cycle) in &mut puzzle.zip(1..) {
+                // println!("cycle {cycle:<5} load: {load:?}");
+                if cycle_found {
+                    return (int_cycle, cycle);
+                }
+            }
+            unreachable!("spin cycles should eventually repeat")
+        });
 
-    for ((_, int_cycle, cycle_found), cycle) in &mut puzzle.zip(1..) {
-        // println!("cycle {cycle:<5} load: {load:?}");
-        if cycle_found {
-            loop_start = int_cycle;
-            loop_length = cycle;
-            break;
+        if let Some(frames_dir) = frames_output() {
+            std::fs::create_dir_all(frames_dir)?;
+            for (i, frame) in puzzle.frames().iter().enumerate() {
+                std::fs::write(frames_dir.join(format!("frame_{i:05}.txt")), to_ascii_string(frame))?;
+            }
         }
-    }
 
-    let offset = (1_000_000_000 - (loop_start + 1)) % (loop_length - loop_start);
-    println!("offset {offset}, answer index: {}, answer: {}", loop_start + offset, puzzle.loads[loop_start + offset]);
-    println!("Gold: {}", puzzle.loads[loop_start + offset]);
+        let offset = (1_000_000_000 - (loop_start + 1)) % (loop_length - loop_start);
+        dbgln!("offset {offset}, answer index: {}, answer: {}", loop_start + offset, puzzle.loads[loop_start + offset]);
+        Ok(puzzle.loads[loop_start + offset])
+    }).transpose()?;
+
+    Ok(Answers::new(None::<usize>, gold))
+}
+
+fn main() -> anyhow::Result<()> {
+    let input = read_input()?;
+    run_repeated(&input, run)?.emit();
 
     Ok(())
 }
@@ -190,16 +249,21 @@ impl Tile {
             _ => panic!("invalid tile"),
         }
     }
+
+    /// Character used for this tile in puzzle input.
+    fn to_char(self) -> char {
+        match self {
+            Self::Empty => '.',
+            Self::Round => 'O',
+            Self::Cube => '#',
+        }
+    }
 }
 
 impl std::fmt::Debug for Tile {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         use std::fmt::Write;
-        match self {
-            Tile::Empty => f.write_char('.'),
-            Tile::Round => f.write_char('O'),
-            Tile::Cube => f.write_char('#'),
-        }
+        f.write_char(self.to_char())
     }
 }
 
@@ -225,17 +289,144 @@ fn parse(input: &str) -> Grid<Tile> {
     Grid::from_vec(buffer, cols)
 }
 
-fn print_puzzle(grid: &Grid<Tile>) -> std::io::Result<()> {
-    use std::io::Write;
+/// How [`print_puzzle`] draws tiles.
+#[derive(Debug, Clone, Copy)]
+enum RenderMode {
+    /// Box drawing characters, see [`Tile`]'s `Display`
+    Unicode,
+    /// Same characters as puzzle input
+    Ascii,
+}
 
-    let mut lock = std::io::stdout().lock();
+/// Format board as text, using the same characters as puzzle input.
+fn to_ascii_string(grid: &Grid<Tile>) -> String {
+    let mut out = String::with_capacity(grid.rows() * (grid.cols() + 1));
     for row in grid.iter_rows() {
         for tile in row {
-            write!(lock, "{tile}")?;
+            out.push(tile.to_char());
         }
-        write!(lock, "\n")?;
+        out.push('\n');
+    }
+    out
+}
+
+fn print_puzzle(grid: &Grid<Tile>, mode: RenderMode) -> std::io::Result<()> {
+    use std::io::Write;
+
+    let mut lock = std::io::stdout().lock();
+    match mode {
+        RenderMode::Unicode => {
+            for row in grid.iter_rows() {
+                for tile in row {
+                    write!(lock, "{tile}")?;
+                }
+                writeln!(lock)?;
+            }
+        },
+        RenderMode::Ascii => write!(lock, "{}", to_ascii_string(grid))?,
     }
     write!(lock, "\n\n")?;
 
     Ok(())
 }
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+O....#....
+O.OO#....#
+.....##...
+OO.#O....O
+.O.....O#.
+O.#..O.#.#
+..O..#O..O
+.......O..
+#....###..
+#OO..#....
+";
+
+    /// Parse grid and assert it renders back to `input`.
+    fn roundtrip(input: &str) {
+        let rendered: Vec<String> = parse(input).iter_rows()
+            .map(|row| row.map(|tile| tile.to_char()).collect())
+            .collect();
+
+        assert_eq!(rendered.join("\n"), input.trim());
+    }
+
+    #[test]
+    fn grid_roundtrip() {
+        roundtrip(EXAMPLE);
+    }
+
+    #[test]
+    fn ascii_roundtrip() {
+        let grid = parse(EXAMPLE);
+        assert_eq!(parse(&to_ascii_string(&grid)), grid);
+    }
+
+    #[test]
+    fn frames_per_quarter_tilt() {
+        let mut puzzle = Puzzle::new(parse(EXAMPLE), true);
+        let _ = (&mut puzzle).next();
+
+        let frames = puzzle.frames();
+        assert_eq!(frames.len(), 4);
+        assert_eq!(calculate_load(&frames[0]), 136);
+        assert_eq!(to_ascii_string(&frames[3]), "\
+.....#....
+....#...O#
+...OO##...
+.OO#......
+.....OOO#.
+.O#...O#.#
+....O#....
+......OOOO
+#...O###..
+#..OO#....
+");
+    }
+
+    #[test]
+    fn single_tick_and_north_tilt() {
+        let mut puzzle = Puzzle::from_input(EXAMPLE);
+        // Only boulders with an empty tile right above them move
+        assert_eq!(puzzle.tick(), 13);
+
+        let mut puzzle = Puzzle::from_input(EXAMPLE);
+        puzzle.tilt(Direction::North);
+        assert_eq!(calculate_load(puzzle.get()), 136);
+    }
+
+    #[test]
+    fn one_spin_cycle() {
+        let mut puzzle = Puzzle::from_input(EXAMPLE);
+        for direction in [Direction::North, Direction::West, Direction::South, Direction::East] {
+            puzzle.tilt(direction);
+        }
+
+        assert_eq!(to_ascii_string(puzzle.get()), "\
+.....#....
+....#...O#
+...OO##...
+.OO#......
+.....OOO#.
+.O#...O#.#
+....O#....
+......OOOO
+#...O###..
+#..OO#....
+");
+    }
+
+    #[test]
+    fn frames_off_by_default() {
+        let mut puzzle = Puzzle::new(parse(EXAMPLE), false);
+        let _ = (&mut puzzle).next();
+
+        assert!(puzzle.frames().is_empty());
+    }
+}
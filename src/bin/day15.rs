@@ -1,4 +1,9 @@
-use advent::read_input;
+use std::collections::HashMap;
+
+use advent::{read_input, run_repeated, part_filter, Answers, dbgln, timed};
+
+/// Number of boxes, and the modulus of the HASH algorithm.
+const BOXES: usize = 256;
 
 #[derive(Debug)]
 enum Op {
@@ -38,35 +43,85 @@ impl Lens {
     }
 }
 
+/// Lenses of a single box, in insertion order.
+///
+/// Removed lenses leave an empty slot behind, so that neither setting nor
+/// removing a lens has to scan or shift the other lenses.
 #[derive(Debug, Clone, Default)]
 struct LightBox {
-    lenses: Vec<Lens>, // may need a linked list if splicing gets intense
+    slots: Vec<Option<Lens>>,
+    /// Slot index of each lens in the box
+    index: HashMap<String, usize>,
+}
+
+impl LightBox {
+    /// Set focal length of a lens, adding it to the back if it's not in the box.
+    ///
+    /// Returns whether an existing lens was modified.
+    fn set(&mut self, id: &str, focal_length: u8) -> bool {
+        if let Some(&slot) = self.index.get(id) {
+            if let Some(lens) = &mut self.slots[slot] {
+                lens.focal_length = focal_length;
+            }
+            true
+        } else {
+            self.index.insert(id.to_string(), self.slots.len());
+            self.slots.push(Some(Lens::new(id, focal_length)));
+            false
+        }
+    }
+
+    /// Remove a lens from the box.
+    ///
+    /// Returns whether the lens was in the box.
+    fn remove(&mut self, id: &str) -> bool {
+        let Some(slot) = self.index.remove(id) else {
+            return false;
+        };
+        self.slots[slot] = None;
+
+        // Compact once most slots are empty, keeps removal amortized O(1)
+        if self.index.len() * 2 < self.slots.len() {
+            self.slots.retain(Option::is_some);
+            for (slot, lens) in self.slots.iter().flatten().enumerate() {
+                self.index.insert(lens.id.clone(), slot);
+            }
+        }
+        true
+    }
+
+    /// Iterate over lenses in insertion order.
+    fn lenses(&self) -> impl Iterator<Item = &Lens> {
+        self.slots.iter().flatten()
+    }
 }
 
 fn gold(input: &str) -> usize {
-    let mut boxes: Vec<LightBox> = vec![LightBox::default(); 256];
+    gold_with_boxes(input, BOXES)
+}
+
+/// Arrange lenses into `n_boxes` boxes, using it as the hash modulus.
+fn gold_with_boxes(input: &str, n_boxes: usize) -> usize {
+    let mut boxes: Vec<LightBox> = vec![LightBox::default(); n_boxes];
 
     for instruction in input.trim().split(',') {
         match Op::from_str(instruction) {
             Op::Set { id, focal_length } => {
-                let index = hash(id.as_bytes());
+                let index = hash(id.as_bytes(), n_boxes);
 
-                if let Some(lens_position) = boxes[index].lenses.iter().position(|elem| elem.id == id) {
-                    println!("[MODIFY] lens '{id}' at index {index} (new focal: {focal_length})   ({instruction})");
-                    boxes[index].lenses[lens_position].focal_length = focal_length;
+                if boxes[index].set(&id, focal_length) {
+                    dbgln!("[MODIFY] lens '{id}' at index {index} (new focal: {focal_length})   ({instruction})");
                 } else {
-                    println!("[INSERT] new lens with id '{id}' to index {index} (focal: {focal_length})   ({instruction})");
-                    boxes[index].lenses.push(Lens::new(&id, focal_length));
+                    dbgln!("[INSERT] new lens with id '{id}' to index {index} (focal: {focal_length})   ({instruction})");
                 }
             },
             Op::Remove { id } => {
-                let index = hash(id.as_bytes());
+                let index = hash(id.as_bytes(), n_boxes);
 
-                if let Some(lens_position) = boxes[index].lenses.iter().position(|elem| elem.id == id) {
-                    println!("[REMOVE] lens with id '{id}' from box {index}   ({instruction})");
-                    boxes[index].lenses.remove(lens_position);
+                if boxes[index].remove(&id) {
+                    dbgln!("[REMOVE] lens with id '{id}' from box {index}   ({instruction})");
                 } else {
-                    println!("[NOOP]   did not find lens with id '{id}' in box {index}   ({instruction})");
+                    dbgln!("[NOOP]   did not find lens with id '{id}' in box {index}   ({instruction})");
                 }
             },
         }
@@ -78,16 +133,24 @@ fn gold(input: &str) -> usize {
 fn silver(input: &str) -> usize {
     let mut sum = 0;
     for part in input.trim().split(',') {
-        sum += hash(part.as_bytes());
+        sum += hash(part.as_bytes(), BOXES);
     }
     sum
 }
 
+/// Solve the puzzle for given input.
+pub fn run(input: &str) -> anyhow::Result<Answers> {
+    let part = part_filter();
+
+    Ok(Answers::new(
+        part.silver(|| timed("silver", || silver(input))),
+        part.gold(|| timed("gold", || gold(input))),
+    ))
+}
+
 fn main() -> anyhow::Result<()> {
     let input = read_input()?;
-
-    println!("Silver: {}", silver(&input));
-    println!("  Gold: {}", gold(&input));
+    run_repeated(&input, run)?.emit();
 
     Ok(())
 }
@@ -95,8 +158,8 @@ fn main() -> anyhow::Result<()> {
 fn calculate_focusing_power(boxes: &[LightBox]) -> usize {
     let mut sum: usize = 0;
 
-    for (lightbox, box_n) in boxes.into_iter().zip(1..) {
-        for (lens, lens_n) in lightbox.lenses.iter().zip(1..) {
+    for (lightbox, box_n) in boxes.iter().zip(1..) {
+        for (lens, lens_n) in lightbox.lenses().zip(1..) {
             sum += box_n * lens_n * lens.focal_length as usize;
         }
     }
@@ -104,14 +167,56 @@ fn calculate_focusing_power(boxes: &[LightBox]) -> usize {
     sum
 }
 
-fn hash(s: &[u8]) -> usize {
+/// Hash `s` into range `0..modulus`.
+fn hash(s: &[u8], modulus: usize) -> usize {
     let mut hash: usize = 0;
 
     for &val in s {
         hash += val as usize;
         hash *= 17;
-        hash %= 256;
+        hash %= modulus;
     }
 
     hash
 }
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn example_focusing_power() {
+        assert_eq!(gold("rn=1,cm-,qp=3,cm=2,qp-,pc=4,ot=9,ab=5,pc-,pc=6,ot=7\n"), 145);
+    }
+
+    #[test]
+    fn box_count() {
+        const EXAMPLE: &str = "rn=1,cm-,qp=3,cm=2,qp-,pc=4,ot=9,ab=5,pc-,pc=6,ot=7";
+        assert_eq!(gold_with_boxes(EXAMPLE, 256), 145);
+
+        // Everything goes to the first box, numbered 1: rn=1 cm=2 ot=7 ab=5 pc=6
+        assert_eq!(gold_with_boxes(EXAMPLE, 1), 1 + 2 * 2 + 3 * 7 + 4 * 5 + 5 * 6);
+    }
+
+    #[test]
+    fn interleaved_set_remove() {
+        // `rn` and `cm` both go to box 0, re-set `rn` moves to the back
+        assert_eq!(gold("rn=1,rn-,rn=2,cm=3,rn-,rn=4"), 3 + 2 * 4);
+        assert_eq!(gold("rn=1,cm=3,rn=4"), 4 + 2 * 3);
+
+        let mut lightbox = LightBox::default();
+        for (i, id) in ["a", "b", "c", "d", "e"].into_iter().enumerate() {
+            lightbox.set(id, i as u8);
+        }
+        for id in ["a", "c", "d"] {
+            assert!(lightbox.remove(id));
+        }
+        assert!(!lightbox.remove("c"));
+        assert!(lightbox.set("e", 9));
+        assert!(!lightbox.set("a", 7));
+
+        let lenses: Vec<(&str, u8)> = lightbox.lenses().map(|lens| (lens.id.as_str(), lens.focal_length)).collect();
+        assert_eq!(lenses, &[("b", 1), ("e", 9), ("a", 7)]);
+    }
+}
@@ -1,6 +1,6 @@
-use std::collections::{HashSet, VecDeque};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
 
-use advent::read_input;
+use advent::{read_input, run_repeated, part_filter, Answers, timed, debug_output, image_output, maybe_dump, parse_grid, progress, wrap_edges};
 use grid::Grid;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -16,6 +16,7 @@ enum Tile {
 enum Dir { Up, Down, Left, Right, }
 
 /// Represents a collision between a [`Beam`] and grid tile
+#[derive(Debug, Clone, Copy)]
 enum Collision<'a> {
     /// Beam hit grid edge and dies out
     Death,
@@ -25,12 +26,22 @@ enum Collision<'a> {
     Split(Beam<'a>, Beam<'a>),
 }
 
+/// How beams behave at grid edges
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Topology {
+    /// Beams exiting the grid die out
+    Bounded,
+    /// Beams exiting the grid re-enter from the opposite edge
+    Torus,
+}
+
 #[derive(Debug, Clone, Copy)]
 struct Beam<'a> {
     col: usize,
     row: usize,
     direction: Dir,
-    grid: &'a Grid<Tile>
+    grid: &'a Grid<Tile>,
+    topology: Topology,
 }
 
 impl<'a> Beam<'a> {
@@ -47,7 +58,12 @@ impl<'a> Beam<'a> {
             panic!("initial beam position is outside of grid boundaries");
         }
 
-        Self { col, row, direction, grid }
+        Self { col, row, direction, grid, topology: Topology::Bounded }
+    }
+
+    /// Sets how this beam (and beams split from it) behave at grid edges.
+    fn with_topology(self, topology: Topology) -> Self {
+        Self { topology, ..self }
     }
 
     /// Get the tile beam is currently on
@@ -58,24 +74,35 @@ impl<'a> Beam<'a> {
     /// Consumes the beam and moves it to given direction.
     ///
     /// Returns [`None`] if beam goes out of grid bounds.
+    /// Never happens with [`Topology::Torus`], where the beam wraps around instead.
     fn moved_to_direction(self, direction: Dir) -> Option<Self> {
-        let (col, row) = match direction {
-            Dir::Up    => (self.col, self.row.checked_sub(1)?),
-            Dir::Down  => (self.col, self.row + 1),
-            Dir::Left  => (self.col.checked_sub(1)?, self.row),
-            Dir::Right => (self.col + 1, self.row),
+        let (rows, cols) = self.grid.size();
+
+        let (col, row) = match self.topology {
+            Topology::Bounded => match direction {
+                Dir::Up    => (self.col, self.row.checked_sub(1)?),
+                Dir::Down  => (self.col, self.row + 1),
+                Dir::Left  => (self.col.checked_sub(1)?, self.row),
+                Dir::Right => (self.col + 1, self.row),
+            },
+            Topology::Torus => match direction {
+                Dir::Up    => (self.col, (self.row + rows - 1) % rows),
+                Dir::Down  => (self.col, (self.row + 1) % rows),
+                Dir::Left  => ((self.col + cols - 1) % cols, self.row),
+                Dir::Right => ((self.col + 1) % cols, self.row),
+            },
         };
 
-        if col >= self.grid.cols() || row >= self.grid.rows() {
+        if col >= cols || row >= rows {
             return None
         }
 
-        Some(Self { col, row, direction, grid: self.grid })
+        Some(Self { col, row, direction, ..self })
     }
 
     /// Copies current beam and moves it to some direction.
     fn copied_to_direction(&self, direction: Dir) -> Option<Self> {
-        self.clone().moved_to_direction(direction)
+        (*self).moved_to_direction(direction)
     }
 
     /// Get beam's current position and direction
@@ -171,47 +198,314 @@ impl<'a> Beam<'a> {
     }
 }
 
-fn solve(start: Beam) -> usize {
-    // Set of seen (energized) tiles and what direction we have traversed them.
-    let mut seen: HashSet<(usize, usize, Dir)> = HashSet::from([start.position()]);
-    // Queue of beams to be handled
-    let mut beams: VecDeque<Beam> = vec![start].into();
+/// Beam simulation which can be driven one collision at a time.
+struct BeamSim<'a> {
+    /// Queue of beams to be handled
+    beams: VecDeque<Beam<'a>>,
+    /// Set of seen (energized) tiles and what direction we have traversed them.
+    seen: HashSet<(usize, usize, Dir)>,
+}
 
-    while let Some(beam) = beams.pop_front() {
-        match beam.collide() {
+impl<'a> BeamSim<'a> {
+    fn new(start: Beam<'a>) -> Self {
+        Self {
+            beams: vec![start].into(),
+            seen: HashSet::from([start.position()]),
+        }
+    }
+
+    /// Collide the next queued beam with its tile.
+    ///
+    /// Returns [`None`] once all beams have died out or looped.
+    fn step(&mut self) -> Option<Collision<'a>> {
+        let collision = self.beams.pop_front()?.collide();
+
+        match collision {
             Collision::Death => {},
             Collision::Continue(beam) => {
                 // Check if we are in a loop
-                if seen.insert(beam.position()) {
-                    beams.push_back(beam);
+                if self.seen.insert(beam.position()) {
+                    self.beams.push_back(beam);
                 }
             },
             Collision::Split(first, second) => {
-                if seen.insert(first.position()) {
-                    beams.push_back(first);
+                if self.seen.insert(first.position()) {
+                    self.beams.push_back(first);
                 }
 
-                if seen.insert(second.position()) {
-                    beams.push_back(second);
+                if self.seen.insert(second.position()) {
+                    self.beams.push_back(second);
                 }
             },
         }
+
+        Some(collision)
+    }
+
+    /// Count tiles energized so far.
+    fn energized(&self) -> usize {
+        // Hacky way to count visited points without directions
+        self.seen.iter()
+            .map(|&(col, row, _)| (col, row))
+            .collect::<HashSet<(usize, usize)>>()
+            .len()
+    }
+}
+
+/// Count tiles energized by a beam starting from `start`.
+fn solve(start: Beam) -> usize {
+    solve_bounded(start, usize::MAX).expect("unbounded simulation always completes")
+}
+
+/// Similar to [`solve`], but gives up with [`None`] once more than
+/// `max_states` beam states have been seen.
+fn solve_bounded(start: Beam, max_states: usize) -> Option<usize> {
+    let mut sim = BeamSim::new(start);
+    while sim.step().is_some() {
+        if sim.seen.len() > max_states {
+            return None;
+        }
+    }
+    Some(sim.energized())
+}
+
+/// Path of a beam from an entry state up to where it splits, dies out or loops.
+#[derive(Debug, Default)]
+struct Segment {
+    /// Tiles along the path, including the entry tile
+    tiles: Vec<(usize, usize)>,
+    /// States of the beams this path splits into, as `(col, row, direction)`
+    exits: Vec<(usize, usize, Dir)>,
+}
+
+impl Segment {
+    fn trace(start: Beam) -> Self {
+        let mut segment = Self::default();
+        let mut visited = HashSet::new();
+        let mut beam = start;
+
+        // Mirrors alone may form a loop, stop once the path repeats
+        while visited.insert(beam.position()) {
+            segment.tiles.push((beam.col, beam.row));
+
+            match beam.collide() {
+                Collision::Death => break,
+                Collision::Continue(next) => beam = next,
+                Collision::Split(first, second) => {
+                    segment.exits = vec![first.position(), second.position()];
+                    break;
+                },
+            }
+        }
+
+        segment
+    }
+}
+
+/// Beam segments by their entry state `(col, row, direction)`.
+///
+/// Edge starts share most of their paths, so tracing each segment once saves
+/// re-simulating beams at the cost of storing the tiles of every segment.
+/// Segments entered mid-path overlap with the ones they were entered from,
+/// so in the worst case memory grows quadratically with the path lengths.
+type SegmentCache = HashMap<(usize, usize, Dir), Segment>;
+
+/// Like [`solve`], but segments of the beam path are reused from `cache`
+/// and new ones are added to it.
+///
+/// The cache is only valid for beams in the same grid and topology.
+fn solve_memoized(start: Beam, cache: &mut SegmentCache) -> usize {
+    let mut energized: HashSet<(usize, usize)> = HashSet::new();
+    let mut seen = HashSet::from([start.position()]);
+    let mut queue = vec![start.position()];
+
+    while let Some(state) = queue.pop() {
+        let segment = cache.entry(state).or_insert_with(|| {
+            let (col, row, direction) = state;
+            Segment::trace(Beam { col, row, direction, ..start })
+        });
+
+        energized.extend(&segment.tiles);
+        for &exit in &segment.exits {
+            if seen.insert(exit) {
+                queue.push(exit);
+            }
+        }
+    }
+
+    energized.len()
+}
+
+/// Count how many times beams starting from `start` enter each tile,
+/// across all directions.
+fn solve_counts(start: Beam) -> Grid<u32> {
+    let mut counts: Grid<u32> = Grid::new(start.grid.rows(), start.grid.cols());
+    counts[(start.row, start.col)] += 1;
+
+    let mut sim = BeamSim::new(start);
+    while let Some(collision) = sim.step() {
+        match collision {
+            Collision::Death => {},
+            Collision::Continue(beam) => counts[(beam.row, beam.col)] += 1,
+            Collision::Split(first, second) => {
+                counts[(first.row, first.col)] += 1;
+                counts[(second.row, second.col)] += 1;
+            },
+        }
     }
 
-    // Hacky way to count visited points without directions
-    seen.into_iter()
-        .map(|(col, row, _)| (col, row))
-        .collect::<HashSet<(usize, usize)>>()
-        .len()
+    counts
 }
 
-fn gold(puzzle: &Grid<Tile>) -> usize {
+/// Number of energized tiles by how many times beams entered them,
+/// one `N visits: M tiles` line per visit count.
+fn format_histogram(counts: &Grid<u32>) -> String {
+    let mut tiles_by_visits: BTreeMap<u32, usize> = BTreeMap::new();
+    for &visits in counts.iter().filter(|&&visits| visits > 0) {
+        *tiles_by_visits.entry(visits).or_default() += 1;
+    }
+
+    tiles_by_visits.into_iter()
+        .map(|(visits, tiles)| format!("{visits} visits: {tiles} tiles\n"))
+        .collect()
+}
+
+/// Propagate a beam starting from `start` until all of its splits have died
+/// out or looped.
+///
+/// Returns a set of energized tiles as `(col, row, direction)`,
+/// the same tile may be present multiple times with different directions.
+fn energize(start: Beam) -> HashSet<(usize, usize, Dir)> {
+    let mut sim = BeamSim::new(start);
+    while sim.step().is_some() {}
+    sim.seen
+}
+
+/// Energized tiles as `(row, col)` without directions, sorted by row and column.
+fn energized_coords(seen: &HashSet<(usize, usize, Dir)>) -> Vec<(usize, usize)> {
+    let mut coords: Vec<(usize, usize)> = seen.iter().map(|&(col, row, _)| (row, col)).collect();
+    coords.sort_unstable();
+    coords.dedup();
+    coords
+}
+
+/// Energized tiles of a grid, unions of masks cover tiles energized by any of them.
+#[derive(Debug, Clone, PartialEq)]
+struct Mask(Grid<bool>);
+
+impl Mask {
+    /// Mask of a `(rows, cols)` grid with no tiles energized.
+    fn empty((rows, cols): (usize, usize)) -> Self {
+        Self(Grid::new(rows, cols))
+    }
+
+    fn count_ones(&self) -> usize {
+        self.0.iter().filter(|&&energized| energized).count()
+    }
+}
+
+impl std::ops::BitOrAssign<&Mask> for Mask {
+    fn bitor_assign(&mut self, rhs: &Mask) {
+        debug_assert_eq!(self.0.size(), rhs.0.size());
+        for (energized, &other) in self.0.iter_mut().zip(rhs.0.iter()) {
+            *energized |= other;
+        }
+    }
+}
+
+impl std::ops::BitOr for &Mask {
+    type Output = Mask;
+
+    fn bitor(self, rhs: &Mask) -> Mask {
+        let mut out = self.clone();
+        out |= rhs;
+        out
+    }
+}
+
+/// Similar to [`solve`], but returns which tiles got energized.
+fn solve_mask(start: Beam) -> Mask {
+    let mut mask = Mask::empty(start.grid.size());
+    for (col, row, _) in energize(start) {
+        mask.0[(row, col)] = true;
+    }
+    mask
+}
+
+/// Similar to [`gold`], but also returns tiles energized by any edge beam.
+fn gold_coverage(puzzle: &Grid<Tile>, topology: Topology) -> (usize, Mask) {
+    let mut coverage = Mask::empty(puzzle.size());
+    let max = gold(puzzle, topology, |start| {
+        let mask = solve_mask(start);
+        coverage |= &mask;
+        mask.count_ones()
+    });
+
+    (max, coverage)
+}
+
+/// Render energized tiles as a binary PPM (P6) image, one pixel per tile.
+/// Energized tiles are white and the rest black.
+fn to_ppm(mask: &Mask) -> Vec<u8> {
+    let (rows, cols) = mask.0.size();
+    let pixels: Vec<u8> = mask.0.iter()
+        .map(|&energized| if energized { u8::MAX } else { 0 })
+        .collect();
+
+    let mut image = format!("P6\n{cols} {rows}\n255\n").into_bytes();
+    // Grayscale, each channel has the same value
+    image.extend(pixels.into_iter().flat_map(|value| [value; 3]));
+    image
+}
+
+/// Draw beam headings of energized tiles in a `(rows, cols)` grid.
+///
+/// Tiles crossed in a single direction get an arrow, tiles crossed in
+/// several directions get the number of directions and the rest are `.`.
+fn format_directions(seen: &HashSet<(usize, usize, Dir)>, (rows, cols): (usize, usize)) -> String {
+    let mut headings: Grid<Vec<Dir>> = Grid::new(rows, cols);
+    for &(col, row, dir) in seen {
+        headings[(row, col)].push(dir);
+    }
+
+    let mut out = String::with_capacity(rows * (cols + 1));
+    for row in headings.iter_rows() {
+        for dirs in row {
+            out.push(match dirs.as_slice() {
+                [] => '.',
+                [Dir::Up] => '^',
+                [Dir::Down] => 'v',
+                [Dir::Left] => '<',
+                [Dir::Right] => '>',
+                many => char::from_digit(many.len() as u32, 10).unwrap(),
+            });
+        }
+        out.push('\n');
+    }
+    out
+}
+
+fn print_directions(seen: &HashSet<(usize, usize, Dir)>, bounds: (usize, usize)) {
+    print!("{}", format_directions(seen, bounds));
+}
+
+/// Find the most tiles energized by a beam entering from any edge,
+/// using `solve` to count energized tiles of a single start.
+fn gold<'a>(
+    puzzle: &'a Grid<Tile>,
+    topology: Topology,
+    mut solve: impl FnMut(Beam<'a>) -> usize,
+) -> usize {
     let (rows, cols) = puzzle.size();
     let mut max: usize = 0;
 
+    // Each edge tile is entered from the outside once
+    let total = 2 * (rows + cols);
+    let mut done = 0;
+
     for col in 0..cols {
-        let downwards_beam = Beam::new_in_grid(col, 0, Dir::Down, &puzzle);
-        let upwards_beam = Beam::new_in_grid(col, rows-1, Dir::Up, &puzzle);
+        let downwards_beam = Beam::new_in_grid(col, 0, Dir::Down, puzzle).with_topology(topology);
+        let upwards_beam = Beam::new_in_grid(col, rows-1, Dir::Up, puzzle).with_topology(topology);
 
         let tiles = solve(downwards_beam);
         if tiles > max {
@@ -222,11 +516,14 @@ fn gold(puzzle: &Grid<Tile>) -> usize {
         if tiles > max {
             max = tiles;
         }
+
+        done += 2;
+        progress(done, total);
     }
 
     for row in 0..rows {
-        let rightward_beam = Beam::new_in_grid(0, row, Dir::Right, &puzzle);
-        let leftward_beam = Beam::new_in_grid(cols-1, row, Dir::Left, &puzzle);
+        let rightward_beam = Beam::new_in_grid(0, row, Dir::Right, puzzle).with_topology(topology);
+        let leftward_beam = Beam::new_in_grid(cols-1, row, Dir::Left, puzzle).with_topology(topology);
 
         let tiles = solve(rightward_beam);
         if tiles > max {
@@ -236,57 +533,272 @@ fn gold(puzzle: &Grid<Tile>) -> usize {
         if tiles > max {
             max = tiles;
         }
+
+        done += 2;
+        progress(done, total);
     }
 
     max
 }
 
-fn main() -> anyhow::Result<()> {
-    let input = read_input()?;
-    let puzzle = parse(&input);
+/// Solve the puzzle for given input.
+pub fn run(input: &str) -> anyhow::Result<Answers> {
+    let puzzle = timed("parse", || parse(input))?;
+    if maybe_dump(&puzzle, |&tile| tile.to_char())? {
+        return Ok(Answers::default());
+    }
 
-    let energized_tiles = solve(Beam::new_in_grid(0, 0, Dir::Right, &puzzle));
-    println!("Silver: {}", energized_tiles);
+    let topology = if wrap_edges() { Topology::Torus } else { Topology::Bounded };
+    let start = Beam::new_in_grid(0, 0, Dir::Right, &puzzle).with_topology(topology);
 
-    let maxed = gold(&puzzle);
-    println!("  Gold: {}", maxed);
+    if debug_output() {
+        let seen = energize(start);
+        print_directions(&seen, puzzle.size());
+        println!("energized tiles: {:?}", energized_coords(&seen));
+        print!("{}", format_histogram(&solve_counts(start)));
+    }
+
+    let part = part_filter();
+
+    // Tiles energized by any of gold's edge beams, only traced when drawn
+    let mut coverage = None;
+    let silver = part.silver(|| timed("silver", || solve(start)));
+    let gold = part.gold(|| timed("gold", || {
+        if image_output().is_some() {
+            let (max, mask) = gold_coverage(&puzzle, topology);
+            coverage = Some(mask);
+            max
+        } else {
+            let mut cache = SegmentCache::new();
+            gold(&puzzle, topology, |start| solve_memoized(start, &mut cache))
+        }
+    }));
+
+    // Draw gold's coverage if it was solved, otherwise silver's beam
+    if let Some(image_path) = image_output() {
+        let mask = coverage.unwrap_or_else(|| solve_mask(start));
+        std::fs::write(image_path, to_ppm(&mask))?;
+    }
+
+    Ok(Answers::new(silver, gold))
+}
+
+fn main() -> anyhow::Result<()> {
+    let input = read_input()?;
+    run_repeated(&input, run)?.emit();
 
     Ok(())
 }
 
-fn parse(s: &str) -> Grid<Tile> {
-    let mut tiles = Vec::new();
-    let cols = s.lines().next().expect("got empty input").len();
-    
-    for tile in s.chars().filter(|ch| !ch.is_ascii_whitespace()) {
-        tiles.push(Tile::from_char(tile));
-    }
+fn parse(s: &str) -> anyhow::Result<Grid<Tile>> {
+    let chars = parse_grid(s, true)?;
+    let tiles = chars.iter()
+        .map(|&tile| Tile::try_from(tile))
+        .collect::<anyhow::Result<Vec<Tile>>>()?;
 
-    Grid::from_vec(tiles, cols)
+    Ok(Grid::from_vec(tiles, chars.cols()))
 }
 
 impl std::fmt::Display for Tile {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         use std::fmt::Write;
+        f.write_char(self.to_char())
+    }
+}
+
+impl TryFrom<char> for Tile {
+    type Error = anyhow::Error;
+
+    fn try_from(value: char) -> Result<Self, Self::Error> {
+        match value {
+            '.' => Some(Self::Empty),
+            '/' => Some(Self::ForwardMirror),
+            '\\' => Some(Self::BackwardMirror),
+            '|' => Some(Self::VertSplit),
+            '-' => Some(Self::HorSplit),
+            _ => None,
+        }.ok_or_else(|| anyhow::anyhow!("invalid tile '{value}'"))
+    }
+}
+
+impl Tile {
+    /// Character used for this tile in puzzle input.
+    fn to_char(self) -> char {
         match self {
-            Tile::Empty => f.write_char('.'),
-            Tile::ForwardMirror => f.write_char('/'),
-            Tile::BackwardMirror => f.write_char('\\'), 
-            Tile::VertSplit => f.write_char('|'), 
-            Tile::HorSplit => f.write_char('-'),
+            Self::Empty => '.',
+            Self::ForwardMirror => '/',
+            Self::BackwardMirror => '\\',
+            Self::VertSplit => '|',
+            Self::HorSplit => '-',
         }
     }
 }
 
-impl Tile {
-    fn from_char(tile: char) -> Tile {
-        match tile {
-            '.' => Self::Empty,
-            '/' => Self::ForwardMirror,
-            '\\' => Self::BackwardMirror,
-            '|' => Self::VertSplit,
-            '-' => Self::HorSplit,
-            _ => panic!("invalid tile '{tile}'"),
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = r"
+.|...\....
+|.-.\.....
+.....|-...
+........|.
+..........
+.........\
+..../.\\..
+.-.-/..|..
+.|....-|.\
+..//.|....
+";
+
+    /// Parse grid and assert it renders back to `input`.
+    fn roundtrip(input: &str) {
+        let rendered: Vec<String> = parse(input.trim_start()).unwrap().iter_rows()
+            .map(|row| row.map(|tile| tile.to_char()).collect())
+            .collect();
+
+        assert_eq!(rendered.join("\n"), input.trim());
+    }
+
+    #[test]
+    fn grid_roundtrip() {
+        roundtrip(EXAMPLE);
+    }
+
+    #[test]
+    fn invalid_tile() {
+        let err = parse(".|.\n.x/\n").unwrap_err();
+        assert_eq!(err.to_string(), "invalid tile 'x'");
+    }
+
+    #[test]
+    fn empty_or_ragged_grid() {
+        let err = parse("").unwrap_err();
+        assert_eq!(err.to_string(), "cannot build a grid from empty input");
+
+        let err = parse(".|.\n./\n").unwrap_err();
+        assert_eq!(err.to_string(), "line 2 has width 2, expected 3");
+    }
+
+    #[test]
+    fn visit_counts() {
+        let grid = parse(EXAMPLE.trim_start()).unwrap();
+        let start = Beam::new_in_grid(0, 0, Dir::Right, &grid);
+
+        let counts = solve_counts(start);
+        assert_eq!(counts.iter().filter(|&&count| count > 0).count(), solve(start));
+        // Some tiles are crossed by beams in several directions
+        assert!(counts.iter().any(|&count| count > 1));
+
+        let histogram = format_histogram(&counts);
+        let tiles: usize = histogram.lines()
+            .map(|line| line.split(' ').nth(2).unwrap().parse::<usize>().unwrap())
+            .sum();
+        assert_eq!(tiles, 46);
+
+        let grid = parse("..\n").unwrap();
+        assert_eq!(format_histogram(&solve_counts(Beam::new_in_grid(0, 0, Dir::Right, &grid))), "1 visits: 2 tiles\n");
+    }
+
+    #[test]
+    fn memoized_gold_matches_naive() {
+        let grid = parse(EXAMPLE.trim_start()).unwrap();
+
+        for (topology, expected) in [(Topology::Bounded, 51), (Topology::Torus, 87)] {
+            let mut cache = SegmentCache::new();
+            assert_eq!(gold(&grid, topology, solve), expected);
+            assert_eq!(gold(&grid, topology, |start| solve_memoized(start, &mut cache)), expected);
+        }
+    }
+
+    #[test]
+    fn state_limit() {
+        let grid = parse(EXAMPLE.trim_start()).unwrap();
+        let start = Beam::new_in_grid(0, 0, Dir::Right, &grid);
+
+        assert_eq!(solve_bounded(start, 10), None);
+        assert_eq!(solve_bounded(start, usize::MAX), Some(46));
+    }
+
+    #[test]
+    fn torus_wraps_around_row() {
+        let grid = parse(".....\n").unwrap();
+        let start = Beam::new_in_grid(2, 0, Dir::Right, &grid);
+
+        assert_eq!(solve(start), 3);
+        assert_eq!(solve(start.with_topology(Topology::Torus)), 5);
+    }
+
+    #[test]
+    fn stepping_matches_solve() {
+        let grid = parse(EXAMPLE.trim_start()).unwrap();
+        let start = Beam::new_in_grid(0, 0, Dir::Right, &grid);
+
+        let mut sim = BeamSim::new(start);
+        let mut splits = 0;
+        while let Some(collision) = sim.step() {
+            if matches!(collision, Collision::Split(..)) {
+                splits += 1;
+            }
         }
+
+        assert!(splits > 0);
+        assert_eq!(sim.energized(), 46);
+        assert_eq!(sim.energized(), solve(start));
+    }
+
+    #[test]
+    fn ppm_marks_energized_tiles() {
+        let grid = parse(EXAMPLE.trim_start()).unwrap();
+        let image = to_ppm(&solve_mask(Beam::new_in_grid(0, 0, Dir::Right, &grid)));
+        let header = b"P6\n10 10\n255\n";
+        assert!(image.starts_with(header));
+
+        let pixels = &image[header.len()..];
+        assert_eq!(pixels.len(), 10 * 10 * 3);
+        assert_eq!(pixels.iter().filter(|&&value| value == u8::MAX).count(), 46 * 3);
+        // Top right corner is never reached, while top left always is
+        assert_eq!(pixels[..3], [u8::MAX; 3]);
+        assert_eq!(pixels[9 * 3..10 * 3], [0; 3]);
+    }
+
+    #[test]
+    fn mask_matches_solve() {
+        let grid = parse(EXAMPLE.trim_start()).unwrap();
+        let start = Beam::new_in_grid(0, 0, Dir::Right, &grid);
+
+        let mask = solve_mask(start);
+        assert_eq!(mask.count_ones(), solve(start));
+        assert_eq!(mask.count_ones(), energized_coords(&energize(start)).len());
+
+        let other = solve_mask(Beam::new_in_grid(3, 0, Dir::Down, &grid));
+        let union = &mask | &other;
+        assert!(union.count_ones() >= mask.count_ones().max(other.count_ones()));
+        assert!(union.count_ones() <= mask.count_ones() + other.count_ones());
+
+        let (max, coverage) = gold_coverage(&grid, Topology::Bounded);
+        assert_eq!(max, 51);
+        assert_eq!(&coverage | &union, coverage);
+    }
+
+    #[test]
+    fn direction_headings() {
+        let grid = parse(".\\.\\.\n.-.|.\n").unwrap();
+        let seen = energize(Beam::new_in_grid(0, 0, Dir::Right, &grid));
+
+        // Beam split upwards is reflected back along the top row,
+        // so the first mirror is crossed both rightwards and leftwards
+        assert_eq!(format_directions(&seen, grid.size()), ">2<^.\n<v>>.\n");
+    }
+
+    #[test]
+    fn energized_coords_sorted() {
+        let grid = parse(EXAMPLE.trim_start()).unwrap();
+        let coords = energized_coords(&energize(Beam::new_in_grid(0, 0, Dir::Right, &grid)));
+
+        assert_eq!(coords.len(), 46);
+        // Tiles on the top two rows
+        assert_eq!(coords[..8], [(0, 0), (0, 1), (0, 2), (0, 3), (0, 4), (0, 5), (1, 1), (1, 5)]);
     }
 }
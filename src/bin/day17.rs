@@ -1,180 +1,589 @@
-use std::collections::{BinaryHeap, HashSet};
+use std::{cmp::Reverse, collections::{BinaryHeap, HashMap, HashSet}, str::FromStr, sync::{Mutex, RwLock}};
 
-use advent::read_input;
+use advent::{read_input, run_repeated, part_filter, bidirectional_search, Answers, timed, dijkstra_path, debug_output, maybe_dump, print_grid_highlighted, parse_digit_grid};
 use grid::Grid;
 
-/// A frontier node in uniform-cost search
-#[derive(Debug, Clone, Copy)]
-struct Node {
-    /// Current cumulative cost
-    cost: usize,
-    /// Metadata fields
-    /// - Position: Used to discover neighbours given some grid
-    /// - Moved: How many times we have moved in a single direction
-    /// - Direction: The direction we entered this node
+/// A search state of the ultra crucible
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct VisitedNode {
+    /// Used to discover neighbours given some grid
     pos: (usize, usize),
     /// How many times we have moved to this `direction` during the search.
     moved: u8,
     /// What direction we came from, used for neighbour discovery
-    direction: Direction,
-}
-
-#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
-struct VisitedNode {
-    pos: (usize, usize),
-    moved: u8,
     direction: Direction
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-enum Direction { Up, Down, Left, Right }
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+enum Direction { Up, Down, Left, Right, UpLeft, UpRight, DownLeft, DownRight }
+
+/// Parses directions by their names, e.g. `UpLeft`.
+impl FromStr for Direction {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL.into_iter()
+            .find(|direction| format!("{direction:?}") == s)
+            .ok_or_else(|| anyhow::anyhow!("invalid direction '{s}'"))
+    }
+}
 
 impl Direction {
+    /// Directions a regular crucible can move to.
+    const ORTHOGONAL: [Direction; 4] = [Self::Up, Self::Down, Self::Left, Self::Right];
+    /// Orthogonal and diagonal directions.
+    const ALL: [Direction; 8] = [
+        Self::Up, Self::Down, Self::Left, Self::Right,
+        Self::UpLeft, Self::UpRight, Self::DownLeft, Self::DownRight,
+    ];
+
     fn as_offset(&self) -> (i8, i8) {
         match self {
-            Direction::Up    => (-1,  0),
-            Direction::Down  => ( 1,  0),
-            Direction::Left  => ( 0, -1),
-            Direction::Right => ( 0,  1),
+            Direction::Up        => (-1,  0),
+            Direction::Down      => ( 1,  0),
+            Direction::Left      => ( 0, -1),
+            Direction::Right     => ( 0,  1),
+            Direction::UpLeft    => (-1, -1),
+            Direction::UpRight   => (-1,  1),
+            Direction::DownLeft  => ( 1, -1),
+            Direction::DownRight => ( 1,  1),
+        }
+    }
+
+    fn reverse(&self) -> Self {
+        match self {
+            Direction::Up        => Direction::Down,
+            Direction::Down      => Direction::Up,
+            Direction::Left      => Direction::Right,
+            Direction::Right     => Direction::Left,
+            Direction::UpLeft    => Direction::DownRight,
+            Direction::UpRight   => Direction::DownLeft,
+            Direction::DownLeft  => Direction::UpRight,
+            Direction::DownRight => Direction::UpLeft,
         }
     }
 }
 
-impl Node {
-    fn new(pos: (usize, usize), cost: usize, moved: u8, direction: Direction) -> Self {
-        Self { pos, cost, moved, direction }
+/// Ultra crucible must move at least this many tiles forward before turning
+const MIN_MOVES: u8 = 4;
+/// Ultra crucible can move at most this many consecutive tiles
+const MAX_MOVES: u8 = 10;
+
+/// Position next to `pos` in given direction, if inside the grid.
+fn step(pos: (usize, usize), direction: Direction, grid: &Grid<u8>) -> Option<(usize, usize)> {
+    let (row_offset, col_offset) = direction.as_offset();
+    let next_row = pos.0.checked_add_signed(row_offset as isize)?;
+    let next_col = pos.1.checked_add_signed(col_offset as isize)?;
+
+    if next_col >= grid.cols() || next_row >= grid.rows() {
+        return None
     }
 
-    /// Discover and return a neighbour in given direction if any
-    fn discover(
-        &self,
-        direction: Direction,
-        grid: &Grid<u8>,
-    ) -> Option<Self> {
-        let (row, col) = self.pos;
-        let (row_offset, col_offset) = direction.as_offset();
-        let next_row = row.checked_add_signed(row_offset as isize)?;
-        let next_col = col.checked_add_signed(col_offset as isize)?;
+    Some((next_row, next_col))
+}
 
-        if next_col >= grid.cols() || next_row >= grid.rows() {
-            return None
-        }
+/// Movement rules of the crucible.
+struct Rules<'a, F> {
+    /// Directions the crucible may move to, reversing is never allowed
+    directions: &'a [Direction],
+    /// Cost of entering a tile with given heat loss, moving to given direction
+    cost: F,
+}
 
-        let new_pos = (next_row, next_col);
+impl VisitedNode {
+    fn new(pos: (usize, usize), moved: u8, direction: Direction) -> Self {
+        Self { pos, moved, direction }
+    }
 
-        // Check move limit
+    /// Discover and return a neighbour in given direction if any,
+    /// along with the cost of entering it.
+    fn discover<F>(
+        &self,
+        direction: Direction,
+        grid: &Grid<u8>,
+        rules: &Rules<F>,
+    ) -> Option<(Self, usize)>
+    where
+        F: Fn(u8, Direction) -> usize,
+    {
+        let new_pos = step(self.pos, direction, grid)?;
+
+        // Check move limit, any change in direction resets the counter
         let moved = if self.direction == direction {
             self.moved + 1
         } else {
-            // Ultra crucible must have moved at least 4 tiles forward before
-            // being able to turn
-            if self.moved < 4 {
+            if self.moved < MIN_MOVES {
                 return None
             }
 
             1
         };
 
-        // Ultra crucible can move a maximum of 10 consecutive tiles
-        if moved > 10 {
+        if moved > MAX_MOVES {
             return None
         }
 
-        Some(Self {
-            pos: new_pos,
-            cost: self.cost + grid[new_pos] as usize,
-            direction,
-            moved,
+        Some((Self::new(new_pos, moved, direction), (rules.cost)(grid[new_pos], direction)))
+    }
+
+    /// Discover all neighbours we can possibly reach.
+    fn neighbors<'a, F>(
+        &self,
+        grid: &'a Grid<u8>,
+        rules: &'a Rules<F>,
+    ) -> impl Iterator<Item = (Self, usize)> + 'a
+    where
+        F: Fn(u8, Direction) -> usize,
+    {
+        let node = *self;
+        node.candidate_directions(rules.directions)
+            .filter_map(move |direction| node.discover(direction, grid, rules))
+    }
+
+    /// Discover all nodes we could have been reached from, i.e. [`Self::neighbors`]
+    /// in reverse. Entering this node costs the same from each of them.
+    fn predecessors<'a, F>(&self, grid: &Grid<u8>, rules: &'a Rules<F>) -> impl Iterator<Item = Self> + 'a {
+        let node = *self;
+        let prev_pos = step(node.pos, node.direction.reverse(), grid);
+
+        // Went straight, so the counter was one less before this move
+        let straight = prev_pos
+            .filter(|_| node.moved > 1)
+            .map(|pos| Self::new(pos, node.moved - 1, node.direction));
+
+        // Turned, so the previous run was long enough to turn after
+        let turns = prev_pos
+            .filter(|_| node.moved == 1)
+            .into_iter()
+            .flat_map(move |pos| {
+                let reverse = node.direction.reverse();
+                rules.directions.iter()
+                    .copied()
+                    .filter(move |&direction| direction != node.direction && direction != reverse)
+                    .flat_map(move |direction| {
+                        (MIN_MOVES..=MAX_MOVES).map(move |moved| Self::new(pos, moved, direction))
+                    })
+            });
+
+        straight.into_iter().chain(turns)
+    }
+
+    /// Directions out of `directions` this node may continue to, i.e. all but reversing.
+    ///
+    /// With orthogonal directions these are turning left, turning right and going forward.
+    fn candidate_directions<'a>(&self, directions: &'a [Direction]) -> impl Iterator<Item = Direction> + 'a {
+        let reverse = self.direction.reverse();
+        directions.iter().copied().filter(move |&direction| direction != reverse)
+    }
+}
+
+/// Find the minimal heat loss from top left to `end`.
+///
+/// Returns the heat loss and the tiles along the path, starting from the
+/// top left tile, whose heat loss isn't counted.
+///
+/// If `bidirectional` is set, searches from both ends at once, see [`solve_bidirectional`].
+fn solve(grid: &Grid<u8>, end: (usize, usize), bidirectional: bool) -> Option<(usize, Vec<(usize, usize)>)> {
+    let rules = Rules {
+        directions: &Direction::ORTHOGONAL,
+        cost: |heat_loss: u8, _| heat_loss as usize,
+    };
+    solve_with(grid, end, &rules, bidirectional)
+}
+
+/// Similar to [`solve`], but with custom movement rules.
+fn solve_with<F>(
+    grid: &Grid<u8>,
+    end: (usize, usize),
+    rules: &Rules<F>,
+    bidirectional: bool,
+) -> Option<(usize, Vec<(usize, usize)>)>
+where
+    F: Fn(u8, Direction) -> usize + Sync,
+{
+    // Already there, e.g. a 1x1 grid where there's nowhere to move
+    if end == (0, 0) {
+        return Some((0, vec![(0, 0)]));
+    }
+
+    // "Root" nodes, starting from top left to all permitted directions.
+    let starts: Vec<(VisitedNode, usize)> = rules.directions.iter()
+        .filter_map(|&direction| {
+            let pos = step((0, 0), direction, grid)?;
+            Some((VisitedNode::new(pos, 1, direction), (rules.cost)(grid[pos], direction)))
         })
+        .collect();
+
+    if bidirectional {
+        return solve_bidirectional(grid, end, rules, starts);
     }
+
+    let (cost, path) = dijkstra_path(starts, |node| node.neighbors(grid, rules), |node| node.pos == end)?;
+
+    // Search starts from the tiles next to top left, so add it back
+    let mut tiles = vec![(0, 0)];
+    tiles.extend(path.iter().map(|node| node.pos));
+    Some((cost, tiles))
 }
 
-fn solve(grid: &Grid<u8>, end: (usize, usize)) -> Option<usize> {
-    let mut frontier: BinaryHeap<Node> = BinaryHeap::new();
-    let mut visited: HashSet<VisitedNode> = HashSet::new();
+/// Index of the forward search in [`Meeting`]
+const FORWARD: usize = 0;
+/// Index of the backward search in [`Meeting`]
+const BACKWARD: usize = 1;
+
+/// Settled states of one search, with their cost and the state they were
+/// reached from.
+type Settled = HashMap<VisitedNode, (usize, Option<VisitedNode>)>;
+
+/// Cheapest path found so far, along with the last forward and first backward
+/// state on it. Either is [`None`] if the path is found by one search alone.
+type Best = Option<(usize, Option<VisitedNode>, Option<VisitedNode>)>;
+
+/// State shared by both searches of [`solve_bidirectional`].
+///
+/// Each lock is only held for a lookup or an update, so the searches
+/// expand their states in parallel.
+#[derive(Default)]
+struct Meeting {
+    /// Costs of settled states of the forward and backward search
+    costs: [RwLock<HashMap<VisitedNode, usize>>; 2],
+    progress: Mutex<Progress>,
+}
 
-    // Insert two "root" nodes, starting from top left.
-    // One going to the right and one going down.
-    let start_down  = (1, 0);
-    let start_right = (0, 1);
-    frontier.push(Node::new(start_down, grid[start_down] as usize, 1, Direction::Down));
-    frontier.push(Node::new(start_right, grid[start_right] as usize, 1, Direction::Right));
+#[derive(Default)]
+struct Progress {
+    /// Cost of the latest state taken from the frontier of each search,
+    /// no unsettled state of that search is cheaper
+    tops: [usize; 2],
+    best: Best,
+}
 
-    while let Some(node) = frontier.pop() {
-        if node.pos == end {
-            return Some(node.cost)
+impl Progress {
+    fn offer(&mut self, cost: usize, forward: Option<VisitedNode>, backward: Option<VisitedNode>) {
+        if self.best.is_none_or(|(best, ..)| best > cost) {
+            self.best = Some((cost, forward, backward));
         }
+    }
+
+    /// Whether neither search can find a cheaper path anymore.
+    fn finished(&self) -> bool {
+        self.best.is_some_and(|(best, ..)| self.tops[FORWARD].saturating_add(self.tops[BACKWARD]) >= best)
+    }
+}
 
-        if !visited.insert(node.into()) {
+/// Similar to [`solve_with`], but searches forward from `starts` and backward
+/// from `end` in separate threads, until the searches meet in the middle.
+///
+/// Forward costs include entering a state, backward costs only the states
+/// after it, so a full path costs their sum at any state along it.
+fn solve_bidirectional<F>(
+    grid: &Grid<u8>,
+    end: (usize, usize),
+    rules: &Rules<F>,
+    starts: Vec<(VisitedNode, usize)>,
+) -> Option<(usize, Vec<(usize, usize)>)>
+where
+    F: Fn(u8, Direction) -> usize + Sync,
+{
+    // The crucible may reach the end with any direction and straight line counter
+    let goals: Vec<(VisitedNode, usize)> = rules.directions.iter()
+        .flat_map(|&direction| (1..=MAX_MOVES).map(move |moved| (VisitedNode::new(end, moved, direction), 0)))
+        .collect();
+
+    let meeting = Meeting::default();
+    let (forward, backward) = std::thread::scope(|scope| {
+        let forward = scope.spawn(|| search_side(FORWARD, starts, &meeting, grid, end, rules));
+        let backward = search_side(BACKWARD, goals, &meeting, grid, end, rules);
+        (forward.join().unwrap(), backward)
+    });
+
+    let (cost, mut last, mut first) = meeting.progress.into_inner().unwrap().best?;
+
+    let mut tiles = Vec::new();
+    while let Some(node) = last {
+        tiles.push(node.pos);
+        last = forward[&node].1;
+    }
+    tiles.push((0, 0));
+    tiles.reverse();
+    while let Some(node) = first {
+        tiles.push(node.pos);
+        first = backward[&node].1;
+    }
+
+    Some((cost, tiles))
+}
+
+/// Run one search of [`solve_bidirectional`] until `meeting` is finished or
+/// there's nothing left to settle, returning the states it settled.
+fn search_side<F>(
+    side: usize,
+    starts: Vec<(VisitedNode, usize)>,
+    meeting: &Meeting,
+    grid: &Grid<u8>,
+    end: (usize, usize),
+    rules: &Rules<F>,
+) -> Settled
+where
+    F: Fn(u8, Direction) -> usize,
+{
+    let other = 1 - side;
+    let mut settled = Settled::new();
+    let mut frontier: BinaryHeap<_> = starts.into_iter()
+        .map(|(node, cost)| Reverse((cost, node, None)))
+        .collect();
+
+    while let Some(Reverse((cost, node, parent))) = frontier.pop() {
+        {
+            let mut progress = meeting.progress.lock().unwrap();
+            progress.tops[side] = cost;
+            if progress.finished() {
+                return settled;
+            }
+        }
+        if settled.contains_key(&node) {
             continue
         }
+        settled.insert(node, (cost, parent));
+        // Settle before looking at the other search, so that of two searches
+        // settling both ends of an edge at once, at least one sees the other
+        meeting.costs[side].write().unwrap().insert(node, cost);
+
+        // States to continue to, with their cost and the meeting they'd make
+        // if the other search has settled them
+        let mut expanded = Vec::new();
+        // Path found by this search alone, reaching the end or the start
+        let mut alone = None;
+        if side == FORWARD {
+            if node.pos == end {
+                alone = Some((cost, Some(node), None));
+            }
+            for (next, edge_cost) in node.neighbors(grid, rules) {
+                expanded.push((next, cost + edge_cost, (Some(node), Some(next))));
+            }
+        } else {
+            // Reversed edges all enter this node
+            let edge_cost = (rules.cost)(grid[node.pos], node.direction);
+            if node.moved == 1 && step((0, 0), node.direction, grid) == Some(node.pos) {
+                alone = Some((edge_cost + cost, None, Some(node)));
+            }
+            for prev in node.predecessors(grid, rules) {
+                expanded.push((prev, edge_cost + cost, (Some(prev), Some(node))));
+            }
+        }
 
-        // Enqueue all neighbours we can possible reach
-        // First, map relative direction to absolute
-        let (left, right, forward) = match node.direction {
-            Direction::Up    => (Direction::Left,  Direction::Right, Direction::Up),
-            Direction::Down  => (Direction::Right, Direction::Left,  Direction::Down),
-            Direction::Left  => (Direction::Down,  Direction::Up,    Direction::Left),
-            Direction::Right => (Direction::Up,    Direction::Down,  Direction::Right),
+        let met = {
+            let costs = meeting.costs[other].read().unwrap();
+            expanded.iter()
+                .filter_map(|&(state, through, (forward, backward))| {
+                    Some((through + costs.get(&state)?, forward, backward))
+                })
+                .chain(alone)
+                .min_by_key(|&(cost, ..)| cost)
         };
+        if let Some((cost, forward, backward)) = met {
+            meeting.progress.lock().unwrap().offer(cost, forward, backward);
+        }
 
-        for direction in [left, right, forward] {
-            if let Some(discovered_node) = node.discover(direction, &grid) {
-                frontier.push(discovered_node)
+        for (state, through, _) in expanded {
+            if !settled.contains_key(&state) {
+                frontier.push(Reverse((through, state, Some(node))));
             }
         }
     }
 
-    None
+    // Everything reachable is settled, the other search may still improve on the best path
+    meeting.progress.lock().unwrap().tops[side] = usize::MAX;
+    settled
 }
 
+/// Serialize search states and their costs as JSON, e.g. to persist
+/// a frontier which can later be resumed by passing it as search starts.
+#[cfg(feature = "serde")]
+#[cfg_attr(not(test), allow(dead_code))]
+fn dump_frontier(frontier: &[(VisitedNode, usize)]) -> serde_json::Result<String> {
+    serde_json::to_string(frontier)
+}
 
-fn main() -> anyhow::Result<()> {
-    let input = read_input()?;
-    let grid = parse(&input);
+/// Inverse of [`dump_frontier`].
+#[cfg(feature = "serde")]
+#[cfg_attr(not(test), allow(dead_code))]
+fn load_frontier(dump: &str) -> serde_json::Result<Vec<(VisitedNode, usize)>> {
+    serde_json::from_str(dump)
+}
 
-    let min_cost = solve(&grid, (grid.rows()-1, grid.cols()-1));
+/// Solve the puzzle for given input.
+pub fn run(input: &str) -> anyhow::Result<Answers> {
+    let grid = timed("parse", || parse_digit_grid(input))?;
+    if maybe_dump(&grid, |&digit| char::from(b'0' + digit))? {
+        return Ok(Answers::default());
+    }
 
-    println!("Gold: {}", min_cost.unwrap());
+    let part = part_filter();
 
-    Ok(())
+    let gold = part.gold(|| -> anyhow::Result<usize> {
+        let (min_cost, path) = timed("gold", || solve(&grid, (grid.rows()-1, grid.cols()-1), bidirectional_search()))
+            .ok_or_else(|| anyhow::anyhow!("no path to the bottom right corner"))?;
+
+        if debug_output() {
+            let path: HashSet<(usize, usize)> = path.into_iter().collect();
+            print_grid_highlighted(&grid, |row, col| path.contains(&(row, col)));
+        }
+
+        Ok(min_cost)
+    }).transpose()?;
+
+    Ok(Answers::new(None::<usize>, gold))
 }
 
-fn parse(s: &str) -> Grid<u8> {
-    let cols = s.lines().next().expect("got empty input").len();
-    let tiles: Vec<u8> = s.chars()
-        .filter_map(|ch| 
-            u8::try_from(ch).ok()
-                .and_then(|n| n.checked_sub(b'0'))
-        ).collect();
+fn main() -> anyhow::Result<()> {
+    let input = read_input()?;
+    run_repeated(&input, run)?.emit();
 
-    Grid::from_vec(tiles, cols)
+    Ok(())
 }
 
-impl Ord for Node {
-    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        // Reversed comparison so we can build a min-heap
-        // (minimize the distance)
-        self.cost.cmp(&other.cost).reverse()
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+2413432311323
+3215453535623
+3255245654254
+3446585845452
+4546657867536
+1438598798454
+4457876987766
+3637877979653
+4654967986887
+4564679986453
+1224686865563
+2546548887735
+4322674655533
+";
+
+    #[test]
+    fn diagonal_shortcut() {
+        let grid = parse_digit_grid("\
+19999
+91999
+99199
+99919
+99991
+").unwrap();
+        let end = (4, 4);
+        let diagonal = Rules {
+            directions: &Direction::ALL,
+            cost: |heat_loss: u8, _| heat_loss as usize,
+        };
+
+        assert_eq!(solve(&grid, end, false).unwrap().0, 7 * 9 + 1);
+
+        let (cost, path) = solve_with(&grid, end, &diagonal, false).unwrap();
+        assert_eq!(cost, 4);
+        assert_eq!(path, &[(0, 0), (1, 1), (2, 2), (3, 3), (4, 4)]);
+        assert_eq!(solve_with(&grid, end, &diagonal, true).unwrap().0, 4);
     }
-}
 
-impl PartialOrd for Node {
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        Some(self.cmp(other))
+    #[test]
+    fn candidate_directions() {
+        use Direction::*;
+        // Incoming direction and its (left, right, forward)
+        let cases = [
+            (Up, [Left, Right, Up]),
+            (Down, [Right, Left, Down]),
+            (Left, [Down, Up, Left]),
+            (Right, [Up, Down, Right]),
+        ];
+
+        for (direction, [left, right, forward]) in cases {
+            let node = VisitedNode::new((1, 1), 1, direction);
+            let mut candidates: Vec<Direction> = node.candidate_directions(&Direction::ORTHOGONAL).collect();
+            let mut expected = vec![left, right, forward];
+            candidates.sort_by_key(|direction| direction.as_offset());
+            expected.sort_by_key(|direction| direction.as_offset());
+            assert_eq!(candidates, expected, "{direction:?}");
+
+            assert_eq!(node.candidate_directions(&Direction::ALL).count(), 7);
+        }
     }
-}
 
-impl PartialEq for Node {
-    fn eq(&self, other: &Self) -> bool {
-        self.cost == other.cost
+    #[test]
+    fn direction_names() {
+        for direction in Direction::ALL {
+            assert_eq!(format!("{direction:?}").parse::<Direction>().unwrap(), direction);
+        }
+        assert!("Sideways".parse::<Direction>().is_err());
     }
-}
 
-impl Eq for Node {}
+    #[cfg(feature = "serde")]
+    #[test]
+    fn frontier_roundtrip() {
+        let node = VisitedNode::new((3, 7), 2, Direction::DownLeft);
+        let json = serde_json::to_string(&node).unwrap();
+        assert_eq!(json, r#"{"pos":[3,7],"moved":2,"direction":"DownLeft"}"#);
+        assert_eq!(serde_json::from_str::<VisitedNode>(&json).unwrap(), node);
+
+        let frontier = vec![(node, 12), (VisitedNode::new((0, 1), 1, Direction::Right), 4)];
+        assert_eq!(load_frontier(&dump_frontier(&frontier).unwrap()).unwrap(), frontier);
+    }
 
-impl From<Node> for VisitedNode {
-    fn from(value: Node) -> Self {
-        Self { pos: value.pos, moved: value.moved, direction: value.direction }
+    #[test]
+    fn tiny_grids() {
+        let grid = parse_digit_grid("7\n").unwrap();
+        assert_eq!(solve(&grid, (0, 0), false), Some((0, vec![(0, 0)])));
+
+        let grid = parse_digit_grid("91234\n").unwrap();
+        assert_eq!(solve(&grid, (0, 4), false), Some((1 + 2 + 3 + 4, vec![(0, 0), (0, 1), (0, 2), (0, 3), (0, 4)])));
+
+        let grid = parse_digit_grid("9\n1\n2\n3\n4\n").unwrap();
+        assert_eq!(solve(&grid, (4, 0), false).unwrap().0, 1 + 2 + 3 + 4);
+    }
+
+    #[test]
+    fn path_heat_loss_matches_cost() {
+        let grid = parse_digit_grid(EXAMPLE).unwrap();
+        let (cost, path) = solve(&grid, (grid.rows()-1, grid.cols()-1), false).unwrap();
+
+        assert_eq!(cost, 94);
+        assert_eq!(path.first(), Some(&(0, 0)));
+        assert_eq!(path.last(), Some(&(grid.rows()-1, grid.cols()-1)));
+        assert_eq!(path[1..].iter().map(|&pos| grid[pos] as usize).sum::<usize>(), cost);
+
+        // Every step moves to an adjacent tile
+        for pair in path.windows(2) {
+            let ((r0, c0), (r1, c1)) = (pair[0], pair[1]);
+            assert_eq!(r0.abs_diff(r1) + c0.abs_diff(c1), 1);
+        }
+    }
+
+    #[test]
+    fn bidirectional_matches_forward() {
+        let grid = parse_digit_grid(EXAMPLE).unwrap();
+        let end = (grid.rows()-1, grid.cols()-1);
+        let (cost, path) = solve(&grid, end, true).unwrap();
+
+        assert_eq!(cost, solve(&grid, end, false).unwrap().0);
+        assert_eq!(cost, 94);
+        assert_eq!(path.first(), Some(&(0, 0)));
+        assert_eq!(path.last(), Some(&end));
+        assert_eq!(path[1..].iter().map(|&pos| grid[pos] as usize).sum::<usize>(), cost);
+        for pair in path.windows(2) {
+            let ((r0, c0), (r1, c1)) = (pair[0], pair[1]);
+            assert_eq!(r0.abs_diff(r1) + c0.abs_diff(c1), 1);
+        }
+
+        // Straight lines, ending before and after the crucible could turn
+        let grid = parse_digit_grid("91234\n").unwrap();
+        for end in [(0, 1), (0, 2), (0, 4)] {
+            assert_eq!(solve(&grid, end, true), solve(&grid, end, false));
+        }
+        let grid = parse_digit_grid("7\n").unwrap();
+        assert_eq!(solve(&grid, (0, 0), true), Some((0, vec![(0, 0)])));
     }
 }
@@ -1,4 +1,4 @@
-use advent::read_input;
+use advent::{read_input, run_repeated, part_filter, Answers, dbgln, shoelace_f64, timed};
 use anyhow::anyhow;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -6,19 +6,21 @@ enum Direction { Up, Down, Left, Right }
 
 #[derive(Debug)]
 struct Digger {
+    /// Vertex the plan starts from and has to return to
+    start: (isize, isize),
     current: (isize, isize),
     vertices: Vec<(isize, isize)>,
     n_boundary_points: usize,
 }
 
 impl Digger {
-    fn new() -> Self {
-        Self { current: (0, 0), vertices: vec![(0, 0)], n_boundary_points: 1 }
+    fn new(start: (isize, isize)) -> Self {
+        Self { start, current: start, vertices: vec![start], n_boundary_points: 1 }
     }
 
     fn dig(&mut self, direction: Direction, amount: usize) {
         self.current = translate(self.current, direction, amount);
-        if self.current == (0, 0) {
+        if self.current == self.start {
             // we've looped back to start;
             // don't push vertex, but record the number of `boundary points - 1`
             // i.e. the distance to start without including start itself.
@@ -30,8 +32,16 @@ impl Digger {
         self.n_boundary_points += amount;
     }
 
-    fn finish(self) -> (Vec<(isize, isize)>, usize) {
-        (self.vertices, self.n_boundary_points)
+    /// Get the polygon's vertices and number of boundary points.
+    ///
+    /// Returns an error if the digger hasn't returned to start, as the area
+    /// of an open loop would be meaningless.
+    fn finish(self) -> anyhow::Result<(Vec<(isize, isize)>, usize)> {
+        if self.current != self.start {
+            return Err(anyhow!("dig plan doesn't close, it ends at {:?}", self.current));
+        }
+
+        Ok((self.vertices, self.n_boundary_points))
     }
 }
 
@@ -46,20 +56,35 @@ fn translate(point: (isize, isize), direction: Direction, amount: usize) -> (isi
     }
 }
 
-fn main() -> anyhow::Result<()> {
-    let input = read_input()?;
-    let (vertices, boundary_points) = parse(&input);
+/// Solve the puzzle for given input.
+pub fn run(input: &str) -> anyhow::Result<Answers> {
+    let (vertices, boundary_points) = timed("parse", || parse(input))?;
+
+    let part = part_filter();
+
+    let silver = part.silver(|| {
+        let area = timed("silver", || shoelace(&vertices).abs());
+        debug_assert!(
+            (shoelace_f64(&vertices, |v| v as f64).abs() - area as f64).abs() <= 0.5,
+            "integer and floating point areas disagree",
+        );
+        // Pick's theorem
+        let interior = area - (boundary_points as isize / 2) + 1;
 
-    let area = shoelace(&vertices).abs();
-    // Pick's theorem
-    let interior = area - (boundary_points as isize / 2) + 1;
+        // println!("vertices:        {vertices:?}");
+        dbgln!("boundary points: {boundary_points}");
+        dbgln!("interior points: {interior}");
+        dbgln!("polygon area:    {area}");
 
-    // println!("vertices:        {vertices:?}");
-    println!("boundary points: {boundary_points}");
-    println!("interior points: {interior}");
-    println!("polygon area:    {area}");
+        boundary_points + interior as usize
+    });
 
-    println!("Silver: {}", boundary_points + interior as usize);
+    Ok(Answers::new(silver, None::<usize>))
+}
+
+fn main() -> anyhow::Result<()> {
+    let input = read_input()?;
+    run_repeated(&input, run)?.emit();
 
     Ok(())
 }
@@ -82,8 +107,8 @@ fn shoelace(vertices: &[(isize, isize)]) -> isize {
     complete_sum / 2
 }
 
-fn parse(s: &str) -> (Vec<(isize, isize)>, usize) {
-    let mut digger = Digger::new();
+fn parse(s: &str) -> anyhow::Result<(Vec<(isize, isize)>, usize)> {
+    let mut digger = Digger::new((0, 0));
 
     for line in s.trim().lines() {
         let mut parts = line.splitn(3, ' ');
@@ -134,3 +159,65 @@ impl TryFrom<char> for Direction {
         Ok(inner)
     }
 }
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn closed_square() {
+        // Colors dig 2 steps per side, i.e. a 3x3 square
+        let plan = "R 1 (#000020)\nD 1 (#000021)\nL 1 (#000022)\nU 1 (#000023)\n";
+        assert_eq!(run(plan).unwrap().silver.as_deref(), Some("9"));
+    }
+
+    #[test]
+    fn open_plan_is_an_error() {
+        let plan = "R 1 (#000060)\nD 1 (#000051)\n";
+        let err = parse(plan).unwrap_err();
+        assert_eq!(err.to_string(), "dig plan doesn't close, it ends at (5, 6)");
+    }
+
+    #[test]
+    fn shifted_start() {
+        use Direction::*;
+        // Silver steps of the example plan
+        let plan = [
+            (Right, 6), (Down, 5), (Left, 2), (Down, 2), (Right, 2), (Down, 2), (Left, 5),
+            (Up, 2), (Left, 1), (Up, 2), (Right, 2), (Up, 3), (Left, 2), (Up, 2),
+        ];
+        let dig = |start| {
+            let mut digger = Digger::new(start);
+            for (direction, amount) in plan {
+                digger.dig(direction, amount);
+            }
+            digger.finish().unwrap()
+        };
+
+        let (vertices, boundary_points) = dig((0, 0));
+        let (shifted, shifted_boundary_points) = dig((-1000, 250));
+
+        assert_eq!(shifted_boundary_points, boundary_points);
+        assert_eq!(shifted.len(), vertices.len());
+        assert_eq!(shifted[0], (-1000, 250));
+        assert_eq!(shoelace(&shifted).abs(), shoelace(&vertices).abs());
+        assert_eq!(shoelace(&vertices).abs() + boundary_points as isize / 2 + 1, 62);
+    }
+
+    #[test]
+    fn odd_area_triangle() {
+        // Twice the area is odd, so integer halving drops half a unit
+        let triangle = [(0, 0), (0, 3), (1, 0)];
+        assert_eq!(shoelace_f64(&triangle, |v| v as f64), -1.5);
+        assert_eq!(shoelace(&triangle), -1);
+
+        let reversed = [(1, 0), (0, 3), (0, 0)];
+        assert_eq!(shoelace_f64(&reversed, |v| v as f64), 1.5);
+        assert_eq!(shoelace(&reversed), 1);
+
+        // Lagoons have whole areas, both agree exactly
+        let square = [(0, 0), (0, 2), (2, 2), (2, 0)];
+        assert_eq!(shoelace_f64(&square, |v| v as f64), shoelace(&square) as f64);
+    }
+}
@@ -1,6 +1,7 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, str::FromStr};
 
-use advent::read_input;
+use advent::{read_input, run_repeated, part_filter, Answers, dbgln, timed};
+use anyhow::anyhow;
 
 #[derive(Debug, Clone, Copy)]
 struct Part {
@@ -20,8 +21,44 @@ impl Part {
         }
     }
 
+    /// Iterate over all fields and their values, in `xmas` order.
+    fn iter(&self) -> impl Iterator<Item = (Field, u32)> + '_ {
+        Field::ALL.into_iter().map(|field| (field, self.get(field)))
+    }
+
     fn score(&self) -> u32 {
-        self.x + self.m + self.a + self.s
+        self.iter().map(|(_, value)| value).sum()
+    }
+
+    /// Parse a part from `{x=..,m=..,a=..,s=..}`, fields may be in any order.
+    fn from_line(line: &str) -> anyhow::Result<Self> {
+        let inner = line.strip_prefix('{').and_then(|s| s.strip_suffix('}'))
+            .ok_or_else(|| anyhow!("part '{line}' is not enclosed in braces"))?;
+
+        // Values indexed by field
+        let mut values: [Option<u32>; 4] = [None; 4];
+        for component in inner.split(',') {
+            let (field, value) = component.split_once('=')
+                .ok_or_else(|| anyhow!("invalid component '{component}' in part '{line}'"))?;
+
+            let field_index = field.parse::<Field>()
+                .map_err(|_| anyhow!("invalid field '{field}' in part '{line}'"))? as usize;
+            let slot = &mut values[field_index];
+            if slot.is_some() {
+                return Err(anyhow!("duplicate field '{field}' in part '{line}'"));
+            }
+            *slot = Some(value.parse::<u32>()?);
+        }
+
+        let get = |field: Field| values[field as usize]
+            .ok_or_else(|| anyhow!("missing field '{}' in part '{line}'", field.as_str()));
+
+        Ok(Self {
+            x: get(Field::X)?,
+            m: get(Field::M)?,
+            a: get(Field::A)?,
+            s: get(Field::S)?,
+        })
     }
 }
 
@@ -45,59 +82,110 @@ struct Condition {
     amount: u32,
 }
 
+impl Condition {
+    fn matches(&self, part: &Part) -> bool {
+        match self.op {
+            Op::LessThan    => part.get(self.field) < self.amount,
+            Op::GreaterThan => part.get(self.field) > self.amount,
+        }
+    }
+}
+
+/// Workflows by name
+type Workflows = HashMap<String, Vec<Rule>>;
+
 #[derive(Debug)]
 enum Rule {
     Conditional(Condition, RuleResult),
     Pass(RuleResult),
 }
 
+/// Final outcome of a part going through the workflows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Verdict {
+    Accept,
+    Reject,
+}
+
+/// Solve the puzzle for given input.
+pub fn run(input: &str) -> anyhow::Result<Answers> {
+    let (workflows, parts) = timed("parse", || parse(input))?;
+
+    dbgln!("workflows: {workflows:?}");
+    dbgln!("parts: {parts:?}");
+
+    let part = part_filter();
+
+    Ok(Answers::new(
+        part.silver(|| timed("silver", || accept_score(&workflows, &parts))).transpose()?,
+        None::<u32>,
+    ))
+}
+
 fn main() -> anyhow::Result<()> {
     let input = read_input()?;
-    let (workflows, parts) = parse(&input);
-
-    println!("workflows: {workflows:?}");
-    println!("parts: {parts:?}");
-
-    let mut accept_score = 0;
-
-    let first = &workflows["in"];
-
-    for part in parts {
-        let mut flow = first.iter();
-        while let Some(rule) = flow.next() {
-            let res = match rule {
-                Rule::Conditional(cmp, res) => {
-                    let should_pass = match cmp.op {
-                        Op::LessThan    => part.get(cmp.field) < cmp.amount,
-                        Op::GreaterThan => part.get(cmp.field) > cmp.amount,
-                    };
-
-                    if should_pass {
-                        res
-                    } else {
-                        continue
-                    }
-                },
-                Rule::Pass(res) => res
-            };
-
-            match res {
-                RuleResult::Accept => {
-                    accept_score += part.score();
-                    break
-                },
-                RuleResult::Reject => break,
-                RuleResult::Next(next) => flow = workflows[next].iter(),
-            }
+    run_repeated(&input, run)?.emit();
+
+    Ok(())
+}
+
+/// Follow a part through the workflows, starting from `in`.
+///
+/// Returns each visited workflow along with the index of the rule which
+/// decided where to go next, and the final verdict. A part matching no rule
+/// of a workflow is rejected there, with the index one past its last rule.
+fn trace(workflows: &Workflows, part: &Part) -> anyhow::Result<(Vec<(String, usize)>, Verdict)> {
+    let mut visited = Vec::new();
+    let mut name = "in";
+
+    loop {
+        let rules = workflows.get(name)
+            .ok_or_else(|| anyhow!("unknown workflow '{name}'"))?;
+        let Some((index, res)) = rules.iter().enumerate()
+            .find_map(|(index, rule)| match rule {
+                Rule::Conditional(cmp, res) => cmp.matches(part).then_some((index, res)),
+                Rule::Pass(res) => Some((index, res)),
+            })
+        else {
+            visited.push((name.to_string(), rules.len()));
+            return Ok((visited, Verdict::Reject));
+        };
+
+        visited.push((name.to_string(), index));
+
+        match res {
+            RuleResult::Accept => return Ok((visited, Verdict::Accept)),
+            RuleResult::Reject => return Ok((visited, Verdict::Reject)),
+            RuleResult::Next(next) => name = next,
         }
     }
+}
 
-    println!("Silver: {}", accept_score);
+/// Verdict of each part, along with the workflow and index of the rule
+/// which decided it.
+fn decisions(workflows: &Workflows, parts: &[Part]) -> anyhow::Result<Vec<(Verdict, String, usize)>> {
+    parts.iter()
+        .map(|part| {
+            let (mut visited, verdict) = trace(workflows, part)?;
+            dbgln!("{part:?}: {visited:?} -> {verdict:?}");
+
+            // Trace always ends in the deciding workflow
+            let (workflow, rule) = visited.pop().expect("trace visits at least `in`");
+            Ok((verdict, workflow, rule))
+        })
+        .collect()
+}
 
-    Ok(())
+/// Sum scores of all parts accepted by the workflows, starting from `in`.
+fn accept_score(workflows: &Workflows, parts: &[Part]) -> anyhow::Result<u32> {
+    Ok(parts.iter()
+        .zip(decisions(workflows, parts)?)
+        .filter(|(_, (verdict, _, _))| *verdict == Verdict::Accept)
+        .map(|(part, _)| part.score())
+        .sum())
 }
 
-fn parse(input: &str) -> (HashMap<String, Vec<Rule>>, Vec<Part>) {
+fn parse(input: &str) -> anyhow::Result<(Workflows, Vec<Part>)> {
     let mut workflows = HashMap::new();
     let mut parts = Vec::new();
 
@@ -127,7 +215,7 @@ fn parse(input: &str) -> (HashMap<String, Vec<Rule>>, Vec<Part>) {
                 let field = &field_op_number[..op_idx];
                 let amount = &field_op_number[op_idx+1..];
 
-                let field = Field::from_str(field);
+                let field = field.parse::<Field>()?;
                 let amount = amount.parse::<u32>().unwrap();
 
                 let op = match op {
@@ -155,34 +243,132 @@ fn parse(input: &str) -> (HashMap<String, Vec<Rule>>, Vec<Part>) {
     // Rest of lines contain parts.
     // Note: The empty line has been consumed by `take_while()`
     for part_line in lines {
-        let part_line = part_line.strip_prefix('{').and_then(|s| s.strip_suffix('}')).unwrap();
-        let mut components = part_line.split(',')
-            .map(|comp| comp.split_once('=').unwrap().1)
-            .map(|n| n.parse::<u32>().unwrap());
-
-        // Assume that components are always yielded in order (xmas)
-        parts.push(Part {
-            x: components.next().unwrap(),
-            m: components.next().unwrap(),
-            a: components.next().unwrap(),
-            s: components.next().unwrap(),
-        });
+        parts.push(Part::from_line(part_line)?);
     }
 
-    (workflows, parts)
+    Ok((workflows, parts))
 }
 
 #[derive(Debug, Clone, Copy)]
 enum Field { X, M, A, S }
 
-impl Field {
-    fn from_str(s: &str) -> Self {
+impl FromStr for Field {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
-            "x" => Self::X,
-            "m" => Self::M,
-            "a" => Self::A,
-            "s" => Self::S,
-            _ => panic!("invalid field '{s}'"),
+            "x" => Ok(Self::X),
+            "m" => Ok(Self::M),
+            "a" => Ok(Self::A),
+            "s" => Ok(Self::S),
+            _ => Err(anyhow!("invalid field '{s}'")),
+        }
+    }
+}
+
+impl Field {
+    /// All fields in `xmas` order.
+    const ALL: [Field; 4] = [Self::X, Self::M, Self::A, Self::S];
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::X => "x",
+            Self::M => "m",
+            Self::A => "a",
+            Self::S => "s",
         }
     }
 }
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+px{a<2006:qkq,m>2090:A,rfg}
+pv{a>1716:R,A}
+lnx{m>1548:A,A}
+rfg{s<537:gd,x>2440:R,A}
+qs{s>3448:A,lnx}
+qkq{x<1416:A,crn}
+crn{x>2662:A,R}
+in{s<1351:px,qqz}
+qqz{s>2770:qs,m<1801:hdj,R}
+gd{a>3333:R,R}
+hdj{m>838:A,pv}
+
+{x=787,m=2655,a=1222,s=2876}
+{x=1679,m=44,a=2067,s=496}
+{x=2036,m=264,a=79,s=2244}
+{x=2461,m=1339,a=466,s=291}
+{x=2127,m=1623,a=2188,s=1013}
+";
+
+    #[test]
+    fn trace_example_parts() {
+        let (workflows, parts) = parse(EXAMPLE).unwrap();
+
+        let (visited, verdict) = trace(&workflows, &parts[0]).unwrap();
+        let visited: Vec<(&str, usize)> = visited.iter().map(|(name, index)| (name.as_str(), *index)).collect();
+        assert_eq!(visited, &[("in", 1), ("qqz", 0), ("qs", 1), ("lnx", 0)]);
+        assert_eq!(verdict, Verdict::Accept);
+
+        // in -> px -> rfg -> gd -> R
+        let (visited, verdict) = trace(&workflows, &parts[1]).unwrap();
+        assert_eq!(visited.len(), 4);
+        assert_eq!(verdict, Verdict::Reject);
+
+        assert_eq!(accept_score(&workflows, &parts).unwrap(), 19114);
+    }
+
+    #[test]
+    fn deciding_rules() {
+        let (workflows, parts) = parse(EXAMPLE).unwrap();
+
+        let decisions = decisions(&workflows, &parts).unwrap();
+        let accepted: Vec<(&str, usize)> = decisions.iter()
+            .filter(|(verdict, _, _)| *verdict == Verdict::Accept)
+            .map(|(_, workflow, rule)| (workflow.as_str(), *rule))
+            .collect();
+        assert_eq!(accepted, &[("lnx", 0), ("pv", 1), ("rfg", 2)]);
+
+        let (verdict, workflow, rule) = &decisions[3];
+        assert_eq!((*verdict, workflow.as_str(), *rule), (Verdict::Reject, "crn", 1));
+    }
+
+    #[test]
+    fn unmatched_or_unknown_workflow() {
+        let (workflows, parts) = parse("in{x<10:A,x>20:R}\n\n{x=5,m=0,a=0,s=0}\n{x=15,m=0,a=0,s=0}\n").unwrap();
+        let (visited, verdict) = trace(&workflows, &parts[1]).unwrap();
+        assert_eq!(visited, [("in".to_string(), 2)]);
+        assert_eq!(verdict, Verdict::Reject);
+        assert_eq!(accept_score(&workflows, &parts).unwrap(), 5);
+
+        let (workflows, parts) = parse("in{x<10:A,nope}\n\n{x=15,m=0,a=0,s=0}\n").unwrap();
+        let err = accept_score(&workflows, &parts).unwrap_err();
+        assert_eq!(err.to_string(), "unknown workflow 'nope'");
+    }
+
+    #[test]
+    fn part_fields_in_any_order() {
+        let part = Part::from_line("{a=1,x=2,m=3,s=4}").unwrap();
+        assert_eq!((part.x, part.m, part.a, part.s), (2, 3, 1, 4));
+        assert_eq!(part.score(), 10);
+    }
+
+    #[test]
+    fn part_missing_or_duplicate_field() {
+        let missing = Part::from_line("{x=1,m=2,a=3}").unwrap_err();
+        assert_eq!(missing.to_string(), "missing field 's' in part '{x=1,m=2,a=3}'");
+
+        let duplicate = Part::from_line("{x=1,m=2,x=3,s=4}").unwrap_err();
+        assert_eq!(duplicate.to_string(), "duplicate field 'x' in part '{x=1,m=2,x=3,s=4}'");
+    }
+
+    #[test]
+    fn part_unknown_field() {
+        let unknown = Part::from_line("{q=1,x=2,m=3,s=4}").unwrap_err();
+        assert_eq!(unknown.to_string(), "invalid field 'q' in part '{q=1,x=2,m=3,s=4}'");
+    }
+}
@@ -1,34 +1,197 @@
+use std::ffi::OsString;
 use std::path::{Path, PathBuf};
-use std::io::{self, Read, Write};
+use std::io::{self, BufRead, BufReader, Read, Write};
 use std::fmt::{Display, Debug};
+use std::str::FromStr;
+use std::sync::{Mutex, OnceLock};
+use std::sync::atomic::{self, AtomicBool};
+use std::collections::{BinaryHeap, HashMap, HashSet, VecDeque};
+use std::hash::Hash;
 
 use grid::Grid;
 
 /// Helper utility for reading advent of code input files.
+///
+/// With the `http` feature, input can also be downloaded from an
+/// `http://` or `https://` URL given in place of a path.
 pub fn read_input() -> Result<String, io::Error> {
     let filename = get_filename_from_args()?;
 
-    if filename == "-" {
-        read_from_stdin()
+    timed("read", || {
+        let input = if filename == "-" {
+            read_from_stdin()?
+        } else if is_url(&filename) {
+            fetch_url(&filename)?
+        } else {
+            let resolved = resolve_path(&filename)?;
+            std::fs::read_to_string(resolved)?
+        };
+
+        Ok(normalize_newlines(input))
+    })
+}
+
+/// Convert `\r\n` line endings into `\n`, so parsers only have to deal with the latter.
+pub fn normalize_newlines(input: String) -> String {
+    if input.contains('\r') {
+        input.replace("\r\n", "\n")
+    } else {
+        input
+    }
+}
+
+/// Similar to [`read_input`], but reads the input lazily line by line.
+pub fn read_input_lines() -> Result<impl Iterator<Item = io::Result<String>>, io::Error> {
+    let filename = get_filename_from_args()?;
+
+    let reader: Box<dyn BufRead> = if filename == "-" {
+        Box::new(io::stdin().lock())
+    } else if is_url(&filename) {
+        Box::new(io::Cursor::new(fetch_url(&filename)?))
     } else {
         let resolved = resolve_path(&filename)?;
-        std::fs::read_to_string(resolved)
+        Box::new(BufReader::new(std::fs::File::open(resolved)?))
+    };
+
+    Ok(reader.lines())
+}
+
+/// Error returned by [`map_input`].
+#[cfg(feature = "mmap")]
+#[derive(Debug)]
+pub enum MapInputError {
+    /// Input file doesn't exist at the resolved path.
+    Missing(PathBuf),
+    /// Input file is empty and can't be mapped.
+    Empty(PathBuf),
+    /// Input is not valid UTF-8, starting from given byte offset.
+    InvalidUtf8(usize),
+    Io(io::Error),
+}
+
+#[cfg(feature = "mmap")]
+impl Display for MapInputError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Missing(path) => write!(f, "input file {} does not exist", path.display()),
+            Self::Empty(path) => write!(f, "input file {} is empty", path.display()),
+            Self::InvalidUtf8(offset) => write!(f, "input is not valid UTF-8 at byte offset {offset}"),
+            Self::Io(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+#[cfg(feature = "mmap")]
+impl std::error::Error for MapInputError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            _ => None,
+        }
     }
 }
 
-/// Similar to [`read_input`], but opens the file via memory mapping.
 #[cfg(feature = "mmap")]
-pub fn map_input() -> Result<memmap2::Mmap, io::Error> {
-    use std::fs::File;
+impl From<io::Error> for MapInputError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
 
+/// Similar to [`read_input`], but opens the file via memory mapping.
+#[cfg(feature = "mmap")]
+pub fn map_input() -> Result<memmap2::Mmap, MapInputError> {
     let filename = get_filename_from_args()?;
-    let resolved = resolve_path(&filename)?;
-    let file = File::open(resolved)?;
+    map_file(&input_path(&filename)?)
+}
+
+/// Memory mapped input, validated as UTF-8.
+#[cfg(feature = "mmap")]
+pub struct MmapStr(memmap2::Mmap);
+
+#[cfg(feature = "mmap")]
+impl MmapStr {
+    /// Validate mapped bytes as UTF-8.
+    fn new(map: memmap2::Mmap) -> Result<Self, MapInputError> {
+        match std::str::from_utf8(&map) {
+            Ok(_) => Ok(Self(map)),
+            Err(err) => Err(MapInputError::InvalidUtf8(err.valid_up_to())),
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        // SAFETY: Contents were validated as UTF-8 in `new`, and the map is read-only.
+        unsafe { std::str::from_utf8_unchecked(&self.0) }
+    }
+}
+
+#[cfg(feature = "mmap")]
+impl std::ops::Deref for MmapStr {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+/// Similar to [`map_input`], but validates input as UTF-8 once,
+/// so it can be used as a `&str` like [`read_input`].
+#[cfg(feature = "mmap")]
+pub fn map_input_str() -> Result<MmapStr, MapInputError> {
+    MmapStr::new(map_input()?)
+}
+
+/// Memory map file at `path`, which must exist and be non-empty.
+#[cfg(feature = "mmap")]
+fn map_file(path: &Path) -> Result<memmap2::Mmap, MapInputError> {
+    let file = match std::fs::File::open(path) {
+        Ok(file) => file,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {
+            return Err(MapInputError::Missing(path.to_path_buf()))
+        },
+        Err(err) => return Err(err.into()),
+    };
 
+    // Mapping a zero-length file either fails or yields nothing useful
+    if file.metadata()?.len() == 0 {
+        return Err(MapInputError::Empty(path.to_path_buf()));
+    }
+
+    // SAFETY: Input files aren't expected to be modified while a puzzle is being solved.
     let map = unsafe { memmap2::Mmap::map(&file) }?;
     Ok(map)
 }
 
+/// Whether input argument is an http(s) URL rather than a path.
+fn is_url(filename: &str) -> bool {
+    filename.starts_with("http://") || filename.starts_with("https://")
+}
+
+/// Download input from `url`, erroring on any non-2xx response.
+#[cfg(feature = "http")]
+fn fetch_url(url: &str) -> Result<String, io::Error> {
+    const TIMEOUT: std::time::Duration = std::time::Duration::from_secs(30);
+
+    let response = ureq::AgentBuilder::new()
+        .timeout(TIMEOUT)
+        .build()
+        .get(url)
+        .call()
+        .map_err(|err| io::Error::other(format!("failed to fetch input: {err}")))?;
+
+    // Redirects are followed, anything else outside 2xx is not input
+    if !(200..300).contains(&response.status()) {
+        return Err(io::Error::other(format!("failed to fetch {url}: status {}", response.status())));
+    }
+
+    response.into_string()
+}
+
+#[cfg(not(feature = "http"))]
+fn fetch_url(url: &str) -> Result<String, io::Error> {
+    Err(io::Error::other(format!("can't fetch {url}, reading input from a URL requires the `http` feature")))
+}
+
 /// Read **unbounded** [`String`] from standard input.
 fn read_from_stdin() -> Result<String, io::Error> {
     let mut buffer = String::new();
@@ -37,24 +200,822 @@ fn read_from_stdin() -> Result<String, io::Error> {
 }
 
 fn get_filename_from_args() -> Result<String, io::Error> {
-    std::env::args().nth(1)
-        .ok_or_else(|| io::Error::new(
-            io::ErrorKind::Other,
-            r#"expected input file path or "-" as first argument"#
-        ))
+    let args = args()?;
+
+    match &args.filename {
+        Some(filename) => Ok(filename.clone()),
+        None if args.example => example_filename(),
+        None => Err(io::Error::other(
+            r#"expected input file path, "-" or --example as an argument"#
+        )),
+    }
+}
+
+/// Example input of the current binary, `examples/<binary>.txt` under the input directory.
+fn example_filename() -> Result<String, io::Error> {
+    let exe = std::env::current_exe()?;
+    let name = exe.file_stem()
+        .and_then(|name| name.to_str())
+        .ok_or_else(|| io::Error::other("cannot determine binary name"))?;
+
+    let filename = format!("examples/{name}.txt");
+    let path = input_path(&filename)?;
+    if !path.exists() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("example input {} does not exist", path.display()),
+        ));
+    }
+
+    Ok(filename)
+}
+
+/// Which parts of the puzzle a binary should solve.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PartFilter {
+    #[default]
+    Both,
+    Silver,
+    Gold,
+}
+
+impl PartFilter {
+    /// Run `f` if silver part was selected.
+    pub fn silver<T>(self, f: impl FnOnce() -> T) -> Option<T> {
+        matches!(self, Self::Both | Self::Silver).then(f)
+    }
+
+    /// Run `f` if gold part was selected.
+    pub fn gold<T>(self, f: impl FnOnce() -> T) -> Option<T> {
+        matches!(self, Self::Both | Self::Gold).then(f)
+    }
+}
+
+impl FromStr for PartFilter {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "both"          => Ok(Self::Both),
+            "silver" | "1"  => Ok(Self::Silver),
+            "gold"   | "2"  => Ok(Self::Gold),
+            _ => Err(format!(r#"invalid part "{s}", expected "silver", "gold" or "both""#)),
+        }
+    }
+}
+
+/// Get parts selected with `--part` flag.
+///
+/// Defaults to [`PartFilter::Both`] if the flag is missing
+/// or arguments couldn't be parsed.
+pub fn part_filter() -> PartFilter {
+    args().map(|args| args.part).unwrap_or_default()
+}
+
+/// Check whether answers should be printed as JSON (`--json` flag).
+pub fn json_output() -> bool {
+    args().map(|args| args.json).unwrap_or_default()
+}
+
+/// Debug output was turned off by [`silence_debug_output`].
+static DEBUG_SILENCED: AtomicBool = AtomicBool::new(false);
+
+/// Check whether debug output should be printed.
+///
+/// Debug output is disabled in JSON mode, so only answers are written to stdout,
+/// and in [`quiet_output`] mode.
+pub fn debug_output() -> bool {
+    !json_output() && !quiet_output() && !DEBUG_SILENCED.load(atomic::Ordering::Relaxed)
+}
+
+/// Check whether debug output was turned off with `--quiet`
+/// or a non-empty, non-zero `AOC_QUIET` environment variable.
+pub fn quiet_output() -> bool {
+    static QUIET: OnceLock<bool> = OnceLock::new();
+
+    *QUIET.get_or_init(|| {
+        let from_env = std::env::var_os("AOC_QUIET")
+            .is_some_and(|value| !value.is_empty() && value != "0");
+
+        from_env || args().is_ok_and(|args| args.quiet)
+    })
+}
+
+/// Turn off debug output for the rest of the run, for binaries printing
+/// answers of their own.
+pub fn silence_debug_output() {
+    DEBUG_SILENCED.store(true, atomic::Ordering::Relaxed);
+}
+
+/// Like [`println!`], but only prints when [`debug_output`] is enabled.
+#[macro_export]
+macro_rules! dbgln {
+    ($($arg:tt)*) => {
+        if $crate::debug_output() {
+            println!($($arg)*);
+        }
+    };
+}
+
+/// Get path given with `--svg` flag, if any.
+pub fn svg_output() -> Option<&'static Path> {
+    args().ok()?.svg.as_deref()
+}
+
+/// Get path given with `--image` flag, if any.
+pub fn image_output() -> Option<&'static Path> {
+    args().ok()?.image.as_deref()
+}
+
+/// Get directory given with `--frames` flag, if any.
+pub fn frames_output() -> Option<&'static Path> {
+    args().ok()?.frames.as_deref()
+}
+
+/// Check whether `--wrap` flag was given, for binaries supporting wraparound grids.
+pub fn wrap_edges() -> bool {
+    args().is_ok_and(|args| args.wrap)
+}
+
+/// Check whether `--ascii` flag was given, for binaries drawing grids
+/// with other characters than their input.
+pub fn ascii_output() -> bool {
+    args().is_ok_and(|args| args.ascii)
+}
+
+/// Check whether `--bidirectional` flag was given, for binaries supporting
+/// searching from both ends at once.
+pub fn bidirectional_search() -> bool {
+    args().is_ok_and(|args| args.bidirectional)
+}
+
+/// Number of times to run the solver, if given with `--repeat N`.
+pub fn repeat_count() -> Option<usize> {
+    args().ok()?.repeat
+}
+
+/// Check whether `--progress` flag was given, for binaries with long running loops.
+pub fn progress_enabled() -> bool {
+    args().is_ok_and(|args| args.progress)
+}
+
+/// Report `done/total` items processed to stderr if [`progress_enabled`].
+///
+/// Updates are rate limited to one per 100ms, the final one is always shown.
+pub fn progress(done: usize, total: usize) {
+    // Progress is best effort, failing to write it isn't worth stopping for
+    let _ = write_progress(&mut io::stderr(), progress_enabled(), done, total);
+}
+
+fn write_progress(out: &mut impl Write, enabled: bool, done: usize, total: usize) -> io::Result<()> {
+    static LAST_UPDATE: Mutex<Option<std::time::Instant>> = Mutex::new(None);
+    const INTERVAL: std::time::Duration = std::time::Duration::from_millis(100);
+
+    if !enabled {
+        return Ok(());
+    }
+
+    let finished = done >= total;
+    {
+        let mut last = LAST_UPDATE.lock().unwrap_or_else(|err| err.into_inner());
+        let now = std::time::Instant::now();
+        if !finished && last.is_some_and(|last| now.duration_since(last) < INTERVAL) {
+            return Ok(());
+        }
+        *last = Some(now);
+    }
+
+    write!(out, "\rprogress: {done}/{total}")?;
+    if finished {
+        writeln!(out)?;
+    }
+    out.flush()
+}
+
+/// Check whether `--dump-grid` flag was given, for binaries parsing a grid.
+pub fn dump_grid() -> bool {
+    args().is_ok_and(|args| args.dump_grid)
+}
+
+/// Print `grid` if [`dump_grid`], with `tile` giving the character of each tile.
+///
+/// Returns whether the grid was printed, so binaries can opt in with
+/// `if maybe_dump(&grid, |tile| tile.to_char())? { return Ok(Answers::default()); }`
+/// and skip solving.
+pub fn maybe_dump<T>(grid: &Grid<T>, tile: impl Fn(&T) -> char) -> io::Result<bool> {
+    write_dump(&mut io::stdout().lock(), grid, tile, dump_grid())
+}
+
+fn write_dump<T>(out: &mut impl Write, grid: &Grid<T>, tile: impl Fn(&T) -> char, enabled: bool) -> io::Result<bool> {
+    if !enabled {
+        return Ok(false);
+    }
+
+    for row in grid.iter_rows() {
+        let line: String = row.map(&tile).collect();
+        writeln!(out, "{line}")?;
+    }
+    writeln!(out)?;
+    Ok(true)
+}
+
+/// Solve `input` with `run`, or `--repeat N` times printing the mean wall
+/// time per run to stderr.
+///
+/// Input is shared between the runs, answers of the last run are returned.
+pub fn run_repeated(
+    input: &str,
+    run: impl Fn(&str) -> anyhow::Result<Answers>,
+) -> anyhow::Result<Answers> {
+    let Some(times) = repeat_count() else {
+        return run(input);
+    };
+
+    let start = std::time::Instant::now();
+    let mut answers = run(input)?;
+    for _ in 1..times {
+        answers = run(input)?;
+    }
+    let mean = start.elapsed().as_secs_f64() / times as f64;
+
+    eprintln!("mean of {times} runs: {:.2}ms", mean * 1000.0);
+    Ok(answers)
+}
+
+/// Check whether timing is enabled, either with `--time` flag
+/// or by setting `AOC_TIME` environment variable.
+pub fn timing_enabled() -> bool {
+    static ENABLED: OnceLock<bool> = OnceLock::new();
+
+    *ENABLED.get_or_init(|| {
+        let from_env = std::env::var_os("AOC_TIME")
+            .is_some_and(|value| !value.is_empty() && value != "0");
+
+        from_env || args().is_ok_and(|args| args.time)
+    })
+}
+
+/// Run `f`, printing its wall time to stderr as `label: 1.23ms`
+/// if [`timing_enabled`].
+pub fn timed<T>(label: &str, f: impl FnOnce() -> T) -> T {
+    if !timing_enabled() {
+        return f();
+    }
+
+    let start = std::time::Instant::now();
+    let out = f();
+    let elapsed = start.elapsed();
+
+    eprintln!("{label}: {:.2}ms", elapsed.as_secs_f64() * 1000.0);
+    out
+}
+
+/// Print puzzle answers.
+///
+/// Parts that weren't solved (see [`part_filter`]) are given as [`None`]
+/// and left out. With `--json`, answers are printed as a single JSON object,
+/// e.g. `{"silver": 1, "gold": 2}`.
+pub fn emit_answers(silver: Option<impl Display>, gold: Option<impl Display>) {
+    if json_output() {
+        let fields: Vec<String> = [
+            silver.map(|answer| format!(r#""silver": {answer}"#)),
+            gold.map(|answer| format!(r#""gold": {answer}"#)),
+        ].into_iter().flatten().collect();
+
+        println!("{{{}}}", fields.join(", "));
+    } else {
+        if let Some(answer) = silver {
+            println!("Silver: {answer}");
+        }
+        if let Some(answer) = gold {
+            println!("  Gold: {answer}");
+        }
+    }
+}
+
+/// Answers of a single puzzle, parts that weren't solved are [`None`].
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Answers {
+    pub silver: Option<String>,
+    pub gold: Option<String>,
+}
+
+impl Answers {
+    pub fn new(silver: Option<impl Display>, gold: Option<impl Display>) -> Self {
+        Self {
+            silver: silver.map(|answer| answer.to_string()),
+            gold: gold.map(|answer| answer.to_string()),
+        }
+    }
+
+    /// Print answers, see [`emit_answers`].
+    pub fn emit(&self) {
+        emit_answers(self.silver.as_ref(), self.gold.as_ref());
+    }
+}
+
+/// Command line arguments shared by all binaries.
+///
+/// `<path | - | --example> [--part silver|gold|both] [--json] [--time] [--svg out.svg]
+/// [--image out.ppm] [--frames dir/] [--wrap] [--repeat N] [--quiet] [--progress]
+/// [--dump-grid] [--bidirectional] [--ascii]`, in any order.
+#[derive(Debug, Default, PartialEq)]
+struct Args {
+    /// Input file path or `-` for standard input
+    filename: Option<String>,
+    /// Read example input of the binary if no path is given
+    example: bool,
+    part: PartFilter,
+    json: bool,
+    time: bool,
+    /// Path to write an SVG visualization to, for binaries that support it
+    svg: Option<PathBuf>,
+    /// Path to write an image to, for binaries that support it
+    image: Option<PathBuf>,
+    /// Directory to write animation frames to, for binaries that support it
+    frames: Option<PathBuf>,
+    /// Wrap around grid edges, for binaries that support it
+    wrap: bool,
+    /// Run the solver this many times, reporting the mean time
+    repeat: Option<usize>,
+    /// Silence debug output
+    quiet: bool,
+    /// Report progress of long runs to stderr
+    progress: bool,
+    /// Print the parsed grid instead of solving, for binaries that support it
+    dump_grid: bool,
+    /// Search from both ends at once, for binaries that support it
+    bidirectional: bool,
+    /// Draw grids with the characters of the input, for binaries that support it
+    ascii: bool,
+}
+
+impl Args {
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut out = Self::default();
+
+        while let Some(arg) = args.next() {
+            // Flags may be given either as `--flag value` or `--flag=value`
+            let (flag, inline_value) = match arg.split_once('=') {
+                Some((flag, value)) if arg.starts_with("--") => (flag, Some(value.to_string())),
+                _ => (arg.as_str(), None),
+            };
+
+            // Value of a flag which requires one
+            let mut value = || inline_value.clone().or_else(|| args.next())
+                .ok_or_else(|| format!("missing value for {flag}"));
+
+            match flag {
+                "--part" => out.part = value()?.parse()?,
+                "--svg" => out.svg = Some(value()?.into()),
+                "--image" => out.image = Some(value()?.into()),
+                "--frames" => out.frames = Some(value()?.into()),
+                "--repeat" => {
+                    let value = value()?;
+                    match value.parse::<usize>() {
+                        Ok(n) if n > 0 => out.repeat = Some(n),
+                        _ => return Err(format!("invalid repeat count {value}, expected a positive integer")),
+                    }
+                },
+                "--json" if inline_value.is_none() => out.json = true,
+                "--time" if inline_value.is_none() => out.time = true,
+                "--example" if inline_value.is_none() => out.example = true,
+                "--wrap" if inline_value.is_none() => out.wrap = true,
+                "--quiet" if inline_value.is_none() => out.quiet = true,
+                "--progress" if inline_value.is_none() => out.progress = true,
+                "--dump-grid" if inline_value.is_none() => out.dump_grid = true,
+                "--bidirectional" if inline_value.is_none() => out.bidirectional = true,
+                "--ascii" if inline_value.is_none() => out.ascii = true,
+                _ if flag.starts_with("--") => return Err(format!("unknown flag {flag}")),
+                _ if out.filename.is_none() => out.filename = Some(arg),
+                _ => return Err(format!("unexpected argument {arg}")),
+            }
+        }
+
+        Ok(out)
+    }
+}
+
+/// Get command line arguments of the current process, parsed once.
+fn args() -> Result<&'static Args, io::Error> {
+    static ARGS: OnceLock<Result<Args, String>> = OnceLock::new();
+
+    ARGS.get_or_init(|| Args::parse(std::env::args().skip(1)))
+        .as_ref()
+        .map_err(|err| io::Error::other(err.clone()))
+}
+
+/// Directory given as an argument, resolved like input file paths.
+///
+/// Defaults to the input directory itself if no directory was given.
+pub fn input_dir() -> Result<PathBuf, io::Error> {
+    input_path(args()?.filename.as_deref().unwrap_or(""))
 }
 
 fn resolve_path(path: impl AsRef<Path>) -> Result<PathBuf, io::Error> {
+    input_path(path)?.canonicalize()
+}
+
+/// Path of an input file, relative paths are looked up from [`input_base`].
+fn input_path(path: impl AsRef<Path>) -> Result<PathBuf, io::Error> {
     let path = path.as_ref();
 
-    if path.is_absolute() {
+    Ok(if path.is_absolute() {
         path.to_path_buf()
     } else {
-        let mut base = std::env::current_dir()?;
-        base.push("inputs/");
+        let mut base = input_base()?;
         base.push(path);
         base
-    }.canonicalize()
+    })
+}
+
+/// Directory relative input paths are looked up from, given by the
+/// `AOC_INPUT_DIR` environment variable, see [`input_dir_from`].
+fn input_base() -> Result<PathBuf, io::Error> {
+    Ok(std::env::current_dir()?.join(input_dir_from(std::env::var_os("AOC_INPUT_DIR"))))
+}
+
+/// Input directory given by a non-empty `var`, or `inputs/` otherwise.
+/// Relative directories are relative to the current directory.
+fn input_dir_from(var: Option<OsString>) -> PathBuf {
+    match var {
+        Some(dir) if !dir.is_empty() => dir.into(),
+        _ => PathBuf::from("inputs/"),
+    }
+}
+
+/// Parse a rectangular [`Grid`] of characters, one row per line.
+///
+/// Empty lines are skipped. Returns an error if any line is of different
+/// width than the first one. Spaces are kept as tiles, see [`parse_grid`].
+pub fn grid_from_str_lines(input: &str) -> Result<Grid<char>, anyhow::Error> {
+    parse_grid(input, false)
+}
+
+/// Parse a rectangular [`Grid`] of characters, one row per line.
+///
+/// Only line breaks (`\n` and `\r`) are dropped by default, so spaces can
+/// be tiles of their own. If `skip_whitespace` is set, all whitespace is
+/// dropped instead. Lines left empty are skipped, and an error is returned
+/// if any line is of different width than the first one.
+pub fn parse_grid(input: &str, skip_whitespace: bool) -> Result<Grid<char>, anyhow::Error> {
+    let is_tile = |ch: &char| *ch != '\r' && !(skip_whitespace && ch.is_whitespace());
+    let mut lines = input.lines()
+        .map(|line| line.chars().filter(is_tile).collect::<Vec<char>>())
+        .enumerate()
+        .filter(|(_, line)| !line.is_empty());
+
+    let (_, mut tiles) = lines.next()
+        .ok_or_else(|| anyhow::anyhow!("cannot build a grid from empty input"))?;
+    let cols = tiles.len();
+
+    for (i, line) in lines {
+        let width = line.len();
+        if width != cols {
+            return Err(anyhow::anyhow!(
+                "line {} has width {width}, expected {cols}", i + 1
+            ));
+        }
+
+        tiles.extend(line);
+    }
+
+    Ok(Grid::from_vec(tiles, cols))
+}
+
+/// Parse a rectangular [`Grid`] of single digits, one row per line.
+///
+/// Empty lines are skipped. Returns an error on any character which isn't
+/// a digit, or if any line is of different width than the first one.
+pub fn parse_digit_grid(input: &str) -> Result<Grid<u8>, anyhow::Error> {
+    let mut cols = None;
+    let mut tiles: Vec<u8> = Vec::new();
+
+    for (i, line) in input.lines().enumerate().filter(|(_, line)| !line.trim().is_empty()) {
+        let line = line.trim_end();
+
+        for (j, ch) in line.chars().enumerate() {
+            let digit = ch.to_digit(10).ok_or_else(|| anyhow::anyhow!(
+                "invalid digit '{ch}' at line {}, column {}", i + 1, j + 1
+            ))?;
+            tiles.push(digit as u8);
+        }
+
+        let width = line.chars().count();
+        match cols {
+            None => cols = Some(width),
+            Some(cols) if cols != width => return Err(anyhow::anyhow!(
+                "line {} has width {width}, expected {cols}", i + 1
+            )),
+            _ => {},
+        }
+    }
+
+    let cols = cols.ok_or_else(|| anyhow::anyhow!("cannot build a grid from empty input"))?;
+    Ok(Grid::from_vec(tiles, cols))
+}
+
+/// Row and column offsets of the four orthogonal neighbours.
+const OFFSETS_4: [(isize, isize); 4] = [
+    (-1,  0),
+    ( 1,  0),
+    ( 0, -1),
+    ( 0,  1),
+];
+
+/// Row and column offsets of all eight neighbours, diagonals last.
+const OFFSETS_8: [(isize, isize); 8] = [
+    (-1,  0),
+    ( 1,  0),
+    ( 0, -1),
+    ( 0,  1),
+    (-1, -1),
+    (-1,  1),
+    ( 1, -1),
+    ( 1,  1),
+];
+
+/// Iterate over orthogonal neighbours of `(row, col)`.
+///
+/// Only neighbours inside grid bounds are yielded, as `(row, col, tile)`.
+pub fn neighbors4<T>(
+    grid: &Grid<T>,
+    row: usize,
+    col: usize,
+) -> impl Iterator<Item = (usize, usize, &T)> {
+    neighbors_with_offsets(grid, row, col, &OFFSETS_4)
+}
+
+/// Similar to [`neighbors4`], but also yields diagonal neighbours.
+pub fn neighbors8<T>(
+    grid: &Grid<T>,
+    row: usize,
+    col: usize,
+) -> impl Iterator<Item = (usize, usize, &T)> {
+    neighbors_with_offsets(grid, row, col, &OFFSETS_8)
+}
+
+fn neighbors_with_offsets<'a, T>(
+    grid: &'a Grid<T>,
+    row: usize,
+    col: usize,
+    offsets: &'static [(isize, isize)],
+) -> impl Iterator<Item = (usize, usize, &'a T)> {
+    offsets.iter().filter_map(move |&(row_offset, col_offset)| {
+        // Bounds checks, usize overflows
+        let next_row = row.checked_add_signed(row_offset)?;
+        let next_col = col.checked_add_signed(col_offset)?;
+
+        grid.get(next_row, next_col).map(|tile| (next_row, next_col, tile))
+    })
+}
+
+/// A frontier entry in [`dijkstra`], ordered by cost only.
+struct Frontier<S> {
+    cost: usize,
+    state: S,
+    /// State this entry was discovered from, [`None`] for start states
+    parent: Option<S>,
+}
+
+impl<S> Ord for Frontier<S> {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        // Reversed comparison so we can build a min-heap
+        self.cost.cmp(&other.cost).reverse()
+    }
+}
+
+impl<S> PartialOrd for Frontier<S> {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<S> PartialEq for Frontier<S> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cost == other.cost
+    }
+}
+
+impl<S> Eq for Frontier<S> {}
+
+/// Uniform-cost search from `starts`, given as `(state, initial_cost)` pairs.
+///
+/// `neighbors` yields `(next_state, edge_cost)` pairs reachable from a state.
+/// Returns the lowest total cost of reaching a state accepted by `goal`.
+pub fn dijkstra<S, FN, IN>(
+    starts: impl IntoIterator<Item = (S, usize)>,
+    neighbors: FN,
+    goal: impl Fn(&S) -> bool,
+) -> Option<usize>
+where
+    S: Hash + Eq + Clone,
+    FN: FnMut(&S) -> IN,
+    IN: IntoIterator<Item = (S, usize)>,
+{
+    dijkstra_path(starts, neighbors, goal).map(|(cost, _)| cost)
+}
+
+/// Similar to [`dijkstra`], but also returns the states along the cheapest
+/// path, from a start state to the goal state.
+pub fn dijkstra_path<S, FN, IN>(
+    starts: impl IntoIterator<Item = (S, usize)>,
+    mut neighbors: FN,
+    goal: impl Fn(&S) -> bool,
+) -> Option<(usize, Vec<S>)>
+where
+    S: Hash + Eq + Clone,
+    FN: FnMut(&S) -> IN,
+    IN: IntoIterator<Item = (S, usize)>,
+{
+    let mut frontier: BinaryHeap<Frontier<S>> = starts.into_iter()
+        .map(|(state, cost)| Frontier { cost, state, parent: None })
+        .collect();
+    // Visited states and the state they were first reached from
+    let mut parents: HashMap<S, Option<S>> = HashMap::new();
+
+    while let Some(Frontier { cost, state, parent }) = frontier.pop() {
+        if goal(&state) {
+            // Walk parent pointers back to a start state
+            let mut path = vec![state];
+            let mut current = parent;
+            while let Some(state) = current {
+                current = parents[&state].clone();
+                path.push(state);
+            }
+            path.reverse();
+
+            return Some((cost, path))
+        }
+
+        if parents.contains_key(&state) {
+            continue
+        }
+        parents.insert(state.clone(), parent);
+
+        for (next, edge_cost) in neighbors(&state) {
+            if !parents.contains_key(&next) {
+                frontier.push(Frontier { cost: cost + edge_cost, state: next, parent: Some(state.clone()) });
+            }
+        }
+    }
+
+    None
+}
+
+/// Breadth-first search from `start`, returning all reachable states.
+///
+/// `neighbors` yields the successors of a state.
+pub fn flood<S, FN, IN>(start: S, mut neighbors: FN) -> HashSet<S>
+where
+    S: Hash + Eq + Clone,
+    FN: FnMut(&S) -> IN,
+    IN: IntoIterator<Item = S>,
+{
+    let mut visited = HashSet::from([start.clone()]);
+    let mut queue = VecDeque::from([start]);
+
+    while let Some(state) = queue.pop_front() {
+        for next in neighbors(&state) {
+            if visited.insert(next.clone()) {
+                queue.push_back(next);
+            }
+        }
+    }
+
+    visited
+}
+
+/// Count orthogonally connected regions of equal tiles in a grid.
+pub fn count_regions<T: PartialEq>(grid: &Grid<T>) -> usize {
+    let mut seen: HashSet<(usize, usize)> = HashSet::new();
+    let mut regions = 0;
+
+    for ((row, col), tile) in grid.indexed_iter() {
+        if seen.contains(&(row, col)) {
+            continue
+        }
+
+        let region = flood((row, col), |&(row, col)| {
+            neighbors4(grid, row, col)
+                .filter(|&(_, _, next)| next == tile)
+                .map(|(row, col, _)| (row, col))
+                .collect::<Vec<_>>()
+        });
+        seen.extend(region);
+        regions += 1;
+    }
+
+    regions
+}
+
+/// Find the first mirror line in a sequence of `len` items given by `source`,
+/// such as rows or columns of a grid.
+///
+/// See [`find_mirrors`] for details.
+pub fn find_mirror<F, I>(source: F, len: usize, smudges: usize) -> Option<usize>
+where
+    F: Fn(usize) -> I,
+    I: IntoIterator,
+    I::Item: PartialEq,
+{
+    find_mirrors(source, len, smudges).next()
+}
+
+/// Find all mirror lines in a sequence of `len` items given by `source`,
+/// where mirrored items differ in exactly `smudges` elements in total.
+///
+/// Mirror lines are given as the number of items before them,
+/// items past either end of the sequence are not compared.
+pub fn find_mirrors<F, I>(source: F, len: usize, smudges: usize) -> impl Iterator<Item = usize>
+where
+    F: Fn(usize) -> I,
+    I: IntoIterator,
+    I::Item: PartialEq,
+{
+    (1..len).filter(move |&pivot| mirror_smudges(&source, pivot, len, smudges) == Some(smudges))
+}
+
+/// Count elements which differ across the mirror line before item `pivot`,
+/// e.g. to find the closest line when [`find_mirrors`] finds none.
+pub fn mirror_differences<F, I>(source: F, pivot: usize, len: usize) -> usize
+where
+    F: Fn(usize) -> I,
+    I: IntoIterator,
+    I::Item: PartialEq,
+{
+    mirror_smudges(source, pivot, len, usize::MAX).unwrap_or(usize::MAX)
+}
+
+/// Check for a mirror before item `pivot` by iteratively expanding two indices.
+///
+/// Example how `i` and `j` move, each column (or similarly a row) must match
+/// in order for the iterator to continue. If either `i` or `j` reach sequence
+/// bounds, the sequence has a mirror at `pivot`.
+///
+/// Returns how many elements differed, unless there are more than `max_smudges`.
+/// ```not_rust
+///     ij
+/// #.##..##.
+/// ---------
+///    i  j
+/// #.##..##.
+/// ---------
+///   i    j
+/// #.##..##.
+/// ```
+fn mirror_smudges<F, I>(source: F, pivot: usize, len: usize, max_smudges: usize) -> Option<usize>
+where
+    F: Fn(usize) -> I,
+    I: IntoIterator,
+    I::Item: PartialEq,
+{
+    let mut smudges = 0;
+
+    for (i, j) in (0..pivot).rev().zip(pivot..len) {
+        // Count how many elements differ between items
+        smudges += source(i).into_iter().zip(source(j))
+            .filter(|(el_i, el_j)| el_i != el_j)
+            .count();
+
+        // Differences are smudges, as long as there aren't too many to clean
+        if smudges > max_smudges {
+            return None
+        }
+    }
+
+    Some(smudges)
+}
+
+/// Rotate grid 90 degrees clockwise, so that the west edge becomes the north edge.
+pub fn rotate_cw<T: Clone>(grid: &Grid<T>) -> Grid<T> {
+    let mut rotated = grid.clone();
+    rotated.rotate_right();
+    rotated
+}
+
+/// Transpose grid, so that columns become rows.
+pub fn transpose<T: Clone>(grid: &Grid<T>) -> Grid<T> {
+    let mut transposed = grid.clone();
+    transposed.transpose();
+    transposed
+}
+
+/// Signed area of a polygon given its vertices, using the shoelace formula
+/// in floating point so the area isn't rounded towards zero.
+///
+/// Coordinates are converted with `to_f64`. Useful to double check integer
+/// shoelace implementations, which may only lose half a unit.
+pub fn shoelace_f64<T: Copy>(vertices: &[(T, T)], to_f64: impl Fn(T) -> f64) -> f64 {
+    let det = |(x1, y1): (T, T), (x2, y2): (T, T)| {
+        to_f64(x1) * to_f64(y2) - to_f64(x2) * to_f64(y1)
+    };
+
+    let sum: f64 = vertices.iter()
+        .zip(vertices.iter().cycle().skip(1))
+        .map(|(&a, &b)| det(a, b))
+        .sum();
+    sum / 2.0
 }
 
 pub fn print_grid<T: Display>(grid: &Grid<T>) {
@@ -69,6 +1030,26 @@ pub fn print_grid<T: Display>(grid: &Grid<T>) {
     writeln!(lock).unwrap();
 }
 
+/// Print grid with tiles accepted by `highlighted` colored green.
+pub fn print_grid_highlighted<T: Display>(grid: &Grid<T>, highlighted: impl Fn(usize, usize) -> bool) {
+    const GREEN: &str = "\x1B[32m";
+    const RESET: &str = "\x1B[0m";
+
+    let mut lock = std::io::stdout().lock();
+
+    for (row, tiles) in grid.iter_rows().enumerate() {
+        for (col, tile) in tiles.enumerate() {
+            if highlighted(row, col) {
+                write!(lock, "{GREEN}{tile}{RESET}").unwrap();
+            } else {
+                write!(lock, "{tile}").unwrap();
+            }
+        }
+        writeln!(lock).unwrap();
+    }
+    writeln!(lock).unwrap();
+}
+
 pub fn print_grid_debug<T: Debug>(grid: &Grid<T>) {
     let mut lock = std::io::stdout().lock();
 
@@ -80,3 +1061,293 @@ pub fn print_grid_debug<T: Debug>(grid: &Grid<T>) {
     }
     writeln!(lock).unwrap();
 }
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_args(args: &[&str]) -> Result<Args, String> {
+        Args::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn args_in_any_order() {
+        let expected = Args {
+            filename: Some("day01.txt".to_string()),
+            part: PartFilter::Gold,
+            ..Default::default()
+        };
+
+        assert_eq!(parse_args(&["day01.txt", "--part", "gold"]), Ok(expected));
+        assert_eq!(parse_args(&["--part", "gold", "day01.txt"]).unwrap().filename.as_deref(), Some("day01.txt"));
+        assert_eq!(parse_args(&["--part=silver", "-"]).unwrap().part, PartFilter::Silver);
+        assert_eq!(parse_args(&["-"]).unwrap().part, PartFilter::Both);
+        assert!(parse_args(&["--json", "-"]).unwrap().json);
+        assert!(parse_args(&["-", "--time"]).unwrap().time);
+        assert_eq!(parse_args(&["--svg", "out.svg", "-"]).unwrap().svg, Some("out.svg".into()));
+        assert_eq!(parse_args(&["-", "--image=out.ppm"]).unwrap().image, Some("out.ppm".into()));
+        assert_eq!(parse_args(&["-", "--frames", "out/"]).unwrap().frames, Some("out/".into()));
+
+        let example = parse_args(&["--example", "--part", "gold"]).unwrap();
+        assert!(example.example);
+        assert!(parse_args(&["-", "--wrap"]).unwrap().wrap);
+        assert_eq!(parse_args(&["-", "--repeat", "10"]).unwrap().repeat, Some(10));
+        assert_eq!(parse_args(&["-"]).unwrap().repeat, None);
+        assert!(parse_args(&["--quiet", "-"]).unwrap().quiet);
+        assert!(!parse_args(&["-"]).unwrap().quiet);
+        assert!(parse_args(&["-", "--progress"]).unwrap().progress);
+        assert!(parse_args(&["--dump-grid", "-"]).unwrap().dump_grid);
+        assert!(parse_args(&["-", "--bidirectional"]).unwrap().bidirectional);
+        assert!(parse_args(&["--ascii", "-"]).unwrap().ascii);
+        assert_eq!(example.filename, None);
+    }
+
+    #[test]
+    fn invalid_args() {
+        assert!(parse_args(&["day01.txt", "--part"]).is_err());
+        assert!(parse_args(&["day01.txt", "--svg"]).is_err());
+        assert!(parse_args(&["day01.txt", "--part", "bronze"]).is_err());
+        assert!(parse_args(&["day01.txt", "--bogus"]).is_err());
+        assert!(parse_args(&["day01.txt", "--json=yes"]).is_err());
+        assert!(parse_args(&["--example=yes"]).is_err());
+        assert!(parse_args(&["day01.txt", "day02.txt"]).is_err());
+        assert!(parse_args(&["day01.txt", "--repeat", "0"]).is_err());
+        assert!(parse_args(&["day01.txt", "--repeat=many"]).is_err());
+        assert!(parse_args(&["day01.txt", "--quiet=1"]).is_err());
+        assert!(parse_args(&["day01.txt", "--progress=yes"]).is_err());
+        assert!(parse_args(&["day01.txt", "--dump-grid=yes"]).is_err());
+    }
+
+    #[test]
+    fn dump_returns_early() {
+        let grid = parse_digit_grid("12\n34\n").unwrap();
+        let digit = |&digit: &u8| char::from(b'0' + digit);
+
+        let mut out = Vec::new();
+        assert!(!write_dump(&mut out, &grid, digit, false).unwrap());
+        assert!(out.is_empty());
+
+        assert!(write_dump(&mut out, &grid, digit, true).unwrap());
+        assert_eq!(String::from_utf8(out).unwrap(), "12\n34\n\n");
+
+        // Flag isn't given to tests
+        assert!(!maybe_dump(&grid, digit).unwrap());
+    }
+
+    #[test]
+    fn progress_only_when_enabled() {
+        let mut out = Vec::new();
+        write_progress(&mut out, false, 1, 2).unwrap();
+        write_progress(&mut out, false, 2, 2).unwrap();
+        assert!(out.is_empty());
+
+        // Final update isn't rate limited
+        write_progress(&mut out, true, 2, 2).unwrap();
+        assert_eq!(out, b"\rprogress: 2/2\n");
+    }
+
+    #[test]
+    fn input_dir_from_env() {
+        let dir = std::env::temp_dir().join("advent-inputs");
+        assert_eq!(input_dir_from(Some(dir.clone().into())), dir);
+        assert_eq!(input_dir_from(Some("puzzles".into())), PathBuf::from("puzzles"));
+        assert_eq!(input_dir_from(Some("".into())), PathBuf::from("inputs/"));
+        assert_eq!(input_dir_from(None), PathBuf::from("inputs/"));
+
+        // Absolute directories replace the current one
+        let cwd = std::env::current_dir().unwrap();
+        assert_eq!(cwd.join(input_dir_from(Some(dir.clone().into()))), dir);
+        assert_eq!(cwd.join(input_dir_from(None)).join("day01.txt"), cwd.join("inputs/day01.txt"));
+    }
+
+    #[test]
+    fn part_filter_runs_selected() {
+        assert_eq!(PartFilter::Silver.silver(|| 1), Some(1));
+        assert_eq!(PartFilter::Silver.gold(|| 2), None);
+        assert_eq!(PartFilter::Both.gold(|| 2), Some(2));
+    }
+
+    #[test]
+    fn grid_from_rectangular_lines() {
+        let grid = grid_from_str_lines("#..\n.#.\n..#\n").unwrap();
+        assert_eq!(grid.size(), (3, 3));
+        assert_eq!(grid[(1, 1)], '#');
+        assert_eq!(grid[(1, 2)], '.');
+    }
+
+    #[test]
+    fn grid_from_ragged_lines() {
+        let err = grid_from_str_lines("#..\n.#\n..#\n").unwrap_err();
+        assert_eq!(err.to_string(), "line 2 has width 2, expected 3");
+
+        let err = grid_from_str_lines("#..\n.#.\n..#.\n").unwrap_err();
+        assert_eq!(err.to_string(), "line 3 has width 4, expected 3");
+    }
+
+    #[test]
+    fn grid_with_spaces() {
+        let input = "+-+\r\n| |\r\n+-+\r\n";
+        let grid = parse_grid(input, false).unwrap();
+        assert_eq!(grid.size(), (3, 3));
+        assert_eq!(grid[(1, 1)], ' ');
+        assert_eq!(grid_from_str_lines(input).unwrap(), grid);
+
+        // Dropping the space makes the middle row too short
+        let err = parse_grid(input, true).unwrap_err();
+        assert_eq!(err.to_string(), "line 2 has width 2, expected 3");
+
+        let grid = parse_grid("a b\n c d\n", true).unwrap();
+        assert_eq!(grid.iter().collect::<String>(), "abcd");
+    }
+
+    #[test]
+    fn grid_from_empty_input() {
+        assert!(grid_from_str_lines("").is_err());
+        assert!(grid_from_str_lines("\n\n").is_err());
+    }
+
+    #[test]
+    fn digit_grid() {
+        let grid = parse_digit_grid("123\n456\n").unwrap();
+        assert_eq!(grid.size(), (2, 3));
+        assert_eq!(grid[(1, 2)], 6);
+
+        let err = parse_digit_grid("123\n4x6\n").unwrap_err();
+        assert_eq!(err.to_string(), "invalid digit 'x' at line 2, column 2");
+        assert!(parse_digit_grid("123\n45\n").is_err());
+        assert!(parse_digit_grid("\n").is_err());
+    }
+
+    #[test]
+    fn grid_from_crlf_lines() {
+        let grid = grid_from_str_lines("ab\r\ncd\r\n").unwrap();
+        assert_eq!(grid.size(), (2, 2));
+        assert_eq!(grid.iter().collect::<String>(), "abcd");
+
+        assert_eq!(normalize_newlines("ab\r\ncd\r\n".to_string()), "ab\ncd\n");
+    }
+
+    #[test]
+    fn dijkstra_on_small_graph() {
+        // 0 -> 1 (4), 0 -> 2 (1), 2 -> 1 (2), 1 -> 3 (1)
+        let edges = |&node: &u8| -> Vec<(u8, usize)> {
+            match node {
+                0 => vec![(1, 4), (2, 1)],
+                1 => vec![(3, 1)],
+                2 => vec![(1, 2)],
+                _ => vec![],
+            }
+        };
+
+        assert_eq!(dijkstra([(0, 0)], edges, |&node| node == 3), Some(4));
+        assert_eq!(dijkstra([(0, 0), (1, 0)], edges, |&node| node == 3), Some(1));
+        assert_eq!(dijkstra([(3, 0)], edges, |&node| node == 0), None);
+        assert_eq!(dijkstra_path([(0, 0)], edges, |&node| node == 3), Some((4, vec![0, 2, 1, 3])));
+        assert_eq!(dijkstra_path([(3, 0)], edges, |&node| node == 3), Some((0, vec![3])));
+    }
+
+    #[test]
+    fn flood_disconnected_regions() {
+        let grid = grid_from_str_lines("##..\n#...\n...#\n..##\n").unwrap();
+
+        let region = flood((0, 0), |&(row, col)| {
+            neighbors4(&grid, row, col)
+                .filter(|&(_, _, &tile)| tile == '#')
+                .map(|(row, col, _)| (row, col))
+                .collect::<Vec<_>>()
+        });
+        assert_eq!(region, HashSet::from([(0, 0), (0, 1), (1, 0)]));
+
+        // Two `#` regions separated by one `.` region
+        assert_eq!(count_regions(&grid), 3);
+    }
+
+    /// All mirror lines between strings, comparing them char by char.
+    fn mirrors(items: &[&str], smudges: usize) -> Vec<usize> {
+        find_mirrors(|i| items[i].chars(), items.len(), smudges).collect()
+    }
+
+    #[test]
+    fn mirrors_in_sequences() {
+        // Perfect mirror between the second and third item
+        let perfect = ["ab", "cd", "cd", "ab", "xy"];
+        assert_eq!(find_mirror(|i| perfect[i].chars(), perfect.len(), 0), Some(2));
+        assert_eq!(mirrors(&perfect, 1), []);
+
+        // Mirror between the first and second item, off by one element
+        let smudged = ["ab", "ac", "xy"];
+        assert_eq!(mirrors(&smudged, 0), []);
+        assert_eq!(mirrors(&smudged, 1), [1]);
+        assert_eq!(mirror_differences(|i| smudged[i].chars(), 1, smudged.len()), 1);
+        assert_eq!(mirror_differences(|i| smudged[i].chars(), 2, smudged.len()), 2);
+
+        assert_eq!(mirrors(&["ab", "cd", "ef"], 0), []);
+        assert_eq!(mirrors(&["ab", "cd", "ef"], 1), []);
+
+        // Every line of a uniform sequence is a mirror
+        assert_eq!(mirrors(&["a", "a", "a"], 0), [1, 2]);
+    }
+
+    #[test]
+    fn rotate_cw_four_times_is_identity() {
+        let grid = grid_from_str_lines("abc\ndef\n").unwrap();
+
+        let once = rotate_cw(&grid);
+        assert_eq!(once.size(), (3, 2));
+        assert_eq!(once.iter_row(0).collect::<String>(), "da");
+
+        let four = rotate_cw(&rotate_cw(&rotate_cw(&once)));
+        assert_eq!(four, grid);
+    }
+
+    #[test]
+    fn transpose_swaps_rows_and_cols() {
+        let grid = grid_from_str_lines("abc\ndef\n").unwrap();
+
+        let transposed = transpose(&grid);
+        assert_eq!(transposed.size(), (3, 2));
+        assert_eq!(transposed.iter_row(1).collect::<String>(), "be");
+        assert_eq!(transpose(&transposed), grid);
+    }
+
+    #[test]
+    fn url_arguments() {
+        assert!(is_url("https://example.com/day01.txt"));
+        assert!(is_url("http://localhost:8000/input"));
+        assert!(!is_url("day01.txt"));
+        assert!(!is_url("/tmp/https://"));
+    }
+
+    #[cfg(not(feature = "http"))]
+    #[test]
+    fn url_needs_http_feature() {
+        let err = fetch_url("https://example.com/").unwrap_err();
+        assert!(err.to_string().contains("`http` feature"), "{err}");
+    }
+
+    #[cfg(feature = "mmap")]
+    #[test]
+    fn map_file_errors() {
+        let dir = std::env::temp_dir().join(format!("advent-mmap-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let empty = dir.join("empty.txt");
+        std::fs::write(&empty, "").unwrap();
+        assert!(matches!(map_file(&empty), Err(MapInputError::Empty(path)) if path == empty));
+
+        let missing = dir.join("missing.txt");
+        assert!(matches!(map_file(&missing), Err(MapInputError::Missing(path)) if path == missing));
+
+        let nonempty = dir.join("nonempty.txt");
+        std::fs::write(&nonempty, "abc").unwrap();
+        assert_eq!(&map_file(&nonempty).unwrap()[..], b"abc");
+        assert_eq!(MmapStr::new(map_file(&nonempty).unwrap()).unwrap().as_str(), "abc");
+
+        let invalid = dir.join("invalid.txt");
+        std::fs::write(&invalid, b"ab\xffc").unwrap();
+        assert!(matches!(MmapStr::new(map_file(&invalid).unwrap()), Err(MapInputError::InvalidUtf8(2))));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}